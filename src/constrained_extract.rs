@@ -0,0 +1,248 @@
+use std::fmt::{self, Debug};
+
+use thiserror::Error;
+
+use crate::util::{HashMap, HashSet};
+use crate::{Analysis, CostFunction, EGraph, Id, Language, RecExpr};
+
+/** Extracting a [`RecExpr`] that only uses some e-nodes and isn't too deep.
+
+Like an [`Extractor`](crate::Extractor), this greedily picks the cheapest
+term of each e-class, but it only considers the e-nodes accepted by a
+[filter](ConstrainedExtractor::with_node_filter), and only terms no deeper
+than a [maximum depth](ConstrainedExtractor::with_max_depth) (a leaf has
+depth 1).
+The cheapest term within the depth limit may not be the cheapest term
+overall, so this keeps track of the cheapest term of each e-class for each
+depth at which it gets cheaper.
+
+Instead of panicking, extracting from an e-class that has no admissible term
+returns an [`ExtractionError`].
+
+```
+use egg::*;
+
+let mut egraph = EGraph::<SymbolLang, ()>::default();
+let a = egraph.add_expr(&"(unsupported x)".parse().unwrap());
+let b = egraph.add_expr(&"(f (f (f x)))".parse().unwrap());
+let c = egraph.add_expr(&"(g (h x) (h y))".parse().unwrap());
+egraph.union(a, b);
+egraph.union(a, c);
+egraph.rebuild();
+
+let extract = |max_depth| {
+    ConstrainedExtractor::new(&egraph, AstSize)
+        .with_node_filter(|node: &SymbolLang| node.op.as_str() != "unsupported")
+        .with_max_depth(max_depth)
+        .find_best(a)
+        .map(|(cost, expr)| (cost, expr.to_string()))
+};
+
+assert_eq!(extract(4), Ok((4, "(f (f (f x)))".to_string())));
+assert_eq!(extract(3), Ok((5, "(g (h x) (h y))".to_string())));
+assert_eq!(extract(2), Err(ExtractionError::NoAdmissibleTerm(egraph.find(a))));
+```
+**/
+pub struct ConstrainedExtractor<'a, CF: CostFunction<L>, L: Language, N: Analysis<L>> {
+    cost_function: CF,
+    node_filter: Box<dyn Fn(&L) -> bool + 'a>,
+    max_depth: usize,
+    levels: Option<Levels<CF::Cost, L>>,
+    egraph: &'a EGraph<L, N>,
+}
+
+/// The cheapest term of an e-class that is at most `depth` deep, recorded
+/// at the smallest depth it was found at.
+#[derive(Debug, Clone)]
+struct Level<C, L> {
+    depth: usize,
+    cost: C,
+    node: L,
+}
+
+/// The [`Level`]s of each e-class, shallowest first.
+type Levels<C, L> = HashMap<Id, Vec<Level<C, L>>>;
+
+/// An error from a [`ConstrainedExtractor`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[non_exhaustive]
+pub enum ExtractionError {
+    /// The e-class has no term that only uses allowed e-nodes and is within
+    /// the depth limit.
+    #[error("e-class {0} has no admissible term")]
+    NoAdmissibleTerm(Id),
+}
+
+impl<'a, CF, L, N> Debug for ConstrainedExtractor<'a, CF, L, N>
+where
+    CF: CostFunction<L> + Debug,
+    L: Language,
+    N: Analysis<L>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Use an exhaustive pattern match to ensure the Debug implementation and the struct stay in sync.
+        let ConstrainedExtractor {
+            cost_function,
+            node_filter: _,
+            max_depth,
+            levels,
+            egraph,
+        } = self;
+
+        f.debug_struct("ConstrainedExtractor")
+            .field("cost_function", cost_function)
+            .field("node_filter", &format_args!("<dyn Fn ..>"))
+            .field("max_depth", max_depth)
+            .field("levels", levels)
+            .field("egraph", egraph)
+            .finish()
+    }
+}
+
+impl<'a, CF, L, N> ConstrainedExtractor<'a, CF, L, N>
+where
+    CF: CostFunction<L>,
+    L: Language,
+    N: Analysis<L>,
+{
+    /// Create a new `ConstrainedExtractor` given an `EGraph` and a
+    /// `CostFunction`.
+    /// By default, all e-nodes are allowed and there is no depth limit.
+    ///
+    /// The costs are computed on the first extraction.
+    pub fn new(egraph: &'a EGraph<L, N>, cost_function: CF) -> Self {
+        Self {
+            cost_function,
+            node_filter: Box::new(|_| true),
+            max_depth: usize::MAX,
+            levels: None,
+            egraph,
+        }
+    }
+
+    /// Only extract e-nodes for which `node_filter` returns `true`.
+    pub fn with_node_filter(self, node_filter: impl Fn(&L) -> bool + 'a) -> Self {
+        Self {
+            node_filter: Box::new(node_filter),
+            levels: None,
+            ..self
+        }
+    }
+
+    /// Only extract terms that are at most `max_depth` deep.
+    /// Default: no limit
+    pub fn with_max_depth(self, max_depth: usize) -> Self {
+        Self {
+            max_depth,
+            levels: None,
+            ..self
+        }
+    }
+
+    /// Find the cheapest admissible `RecExpr` in the given eclass.
+    pub fn find_best(&mut self, eclass: Id) -> Result<(CF::Cost, RecExpr<L>), ExtractionError> {
+        let cost = self.find_best_cost(eclass)?;
+        let eclass = self.egraph.find(eclass);
+        let mut expr = RecExpr::default();
+        self.build_expr(eclass, self.max_depth, &mut expr, &mut HashMap::default());
+        Ok((cost, expr))
+    }
+
+    /// Find the cost of the term that would be extracted from this e-class.
+    pub fn find_best_cost(&mut self, eclass: Id) -> Result<CF::Cost, ExtractionError> {
+        let eclass = self.egraph.find(eclass);
+        let levels = self.levels();
+        match levels.get(&eclass).and_then(|l| l.last()) {
+            Some(level) => Ok(level.cost.clone()),
+            None => Err(ExtractionError::NoAdmissibleTerm(eclass)),
+        }
+    }
+
+    /// The cheapest term of each e-class, computed one depth at a time.
+    /// An e-class can only get cheaper at a depth if one of its children
+    /// got cheaper at the depth before, so only their parents are revisited.
+    fn levels(&mut self) -> &Levels<CF::Cost, L> {
+        if self.levels.is_none() {
+            let egraph = self.egraph;
+            let mut levels: Levels<CF::Cost, L> = HashMap::default();
+            let mut todo: HashSet<Id> = egraph.classes().map(|c| c.id).collect();
+            let mut depth = 1;
+            while !todo.is_empty() && depth <= self.max_depth {
+                let mut found = vec![];
+                for &id in &todo {
+                    if let Some(level) = self.make_pass(&levels, id, depth) {
+                        found.push((id, level));
+                    }
+                }
+
+                todo.clear();
+                for (id, level) in found {
+                    levels.entry(id).or_default().push(level);
+                    for (_, parent) in &egraph[id].parents {
+                        todo.insert(egraph.find(*parent));
+                    }
+                }
+                depth += 1;
+            }
+            self.levels = Some(levels);
+        }
+        self.levels.as_ref().unwrap()
+    }
+
+    /// The cheapest allowed e-node of the e-class whose children all have
+    /// a term, if it's cheaper than the e-class's current term.
+    fn make_pass(
+        &mut self,
+        levels: &Levels<CF::Cost, L>,
+        id: Id,
+        depth: usize,
+    ) -> Option<Level<CF::Cost, L>> {
+        let egraph = self.egraph;
+        let mut best: Option<(CF::Cost, &L)> = None;
+        for node in egraph[id].iter() {
+            let has_cost = |child| levels.contains_key(&egraph.find(child));
+            if !(self.node_filter)(node) || !node.all(has_cost) {
+                continue;
+            }
+            let cost = self.cost_function.cost(node, |child| {
+                levels[&egraph.find(child)].last().unwrap().cost.clone()
+            });
+            if best.as_ref().map_or(true, |(b, _)| cost < *b) {
+                best = Some((cost, node));
+            }
+        }
+
+        let (cost, node) = best?;
+        let current = levels.get(&id).and_then(|l| l.last());
+        if current.map_or(true, |c| cost < c.cost) {
+            Some(Level {
+                depth,
+                cost,
+                node: node.clone(),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn build_expr(
+        &self,
+        eclass: Id,
+        max_depth: usize,
+        expr: &mut RecExpr<L>,
+        built: &mut HashMap<(Id, usize), Id>,
+    ) -> Id {
+        let levels = &self.levels.as_ref().unwrap()[&eclass];
+        let level = levels.iter().rev().find(|l| l.depth <= max_depth).unwrap();
+        if let Some(&id) = built.get(&(eclass, level.depth)) {
+            return id;
+        }
+        let node = level.node.clone().map_children(|child| {
+            let child = self.egraph.find(child);
+            self.build_expr(child, level.depth - 1, expr, built)
+        });
+        let id = expr.add(node);
+        built.insert((eclass, level.depth), id);
+        id
+    }
+}
@@ -0,0 +1,405 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+use crate::util::{HashMap, HashSet, Instant};
+use crate::*;
+
+/// A cost function for the extractors that count shared subterms only once:
+/// [`DagExtractor`] and `LpExtractor` (with the `lp` feature).
+pub trait LpCostFunction<L: Language, N: Analysis<L>> {
+    /// Returns the cost of the given e-node.
+    ///
+    /// This function may look at other parts of the e-graph to compute the cost
+    /// of the given e-node.
+    fn node_cost(&mut self, egraph: &EGraph<L, N>, eclass: Id, enode: &L) -> f64;
+}
+
+impl<L: Language, N: Analysis<L>> LpCostFunction<L, N> for AstSize {
+    fn node_cost(&mut self, _egraph: &EGraph<L, N>, _eclass: Id, _enode: &L) -> f64 {
+        1.0
+    }
+}
+
+/** Extracting terms with the lowest DAG cost, without an ILP solver.
+
+Like `LpExtractor`, this counts the cost of a shared subterm only once,
+so it can pick a bigger tree that is a smaller DAG.
+It takes the same [`LpCostFunction`], so you can switch between them.
+
+By default, this uses a greedy algorithm: for each e-class, it picks the
+e-node whose sub-DAG is cheapest, given the choices made for its children.
+Like [`Extractor`], this never picks an e-node that leads to a cycle.
+This is fast but not always optimal, since two children may be cheaper to
+extract together than on their own.
+[`with_branch_and_bound`](DagExtractor::with_branch_and_bound())
+then searches for the optimal choice, starting from the greedy one, until
+it proves it optimal or runs out of time.
+This takes time exponential in the number of e-classes, so it is only
+feasible for small e-graphs.
+
+# Example
+```
+use egg::*;
+let mut egraph = EGraph::<SymbolLang, ()>::default();
+
+let f = egraph.add_expr(&"(f x x x)".parse().unwrap());
+let g = egraph.add_expr(&"(g (g x))".parse().unwrap());
+egraph.union(f, g);
+egraph.rebuild();
+
+let best = Extractor::new(&egraph, AstSize).find_best(f).1;
+let dag_best = DagExtractor::new(&egraph, AstSize).solve(f);
+
+// In regular extraction, cost is measured on the tree.
+assert_eq!(best.to_string(), "(g (g x))");
+
+// DAG extraction only counts common sub-expressions once,
+// so it can lead to a smaller DAG expression.
+assert_eq!(dag_best.to_string(), "(f x x x)");
+assert_eq!(dag_best.as_ref().len(), 2);
+```
+**/
+#[derive(Debug)]
+pub struct DagExtractor<'a, L: Language, N: Analysis<L>> {
+    egraph: &'a EGraph<L, N>,
+    node_costs: HashMap<Id, Vec<f64>>,
+    time_limit: Option<Duration>,
+}
+
+impl<'a, L, N> DagExtractor<'a, L, N>
+where
+    L: Language,
+    N: Analysis<L>,
+{
+    /// Create a [`DagExtractor`] using costs from the given [`LpCostFunction`].
+    pub fn new<CF>(egraph: &'a EGraph<L, N>, mut cost_function: CF) -> Self
+    where
+        CF: LpCostFunction<L, N>,
+    {
+        let node_costs = egraph
+            .classes()
+            .map(|class| {
+                let costs = class
+                    .iter()
+                    .map(|node| cost_function.node_cost(egraph, class.id, node))
+                    .collect();
+                (class.id, costs)
+            })
+            .collect();
+        Self {
+            egraph,
+            node_costs,
+            time_limit: None,
+        }
+    }
+
+    /// Improve on the greedy extraction with an exact branch and bound
+    /// search, which gives up after the given time and returns the best
+    /// solution it found.
+    pub fn with_branch_and_bound(self, time_limit: Duration) -> Self {
+        Self {
+            time_limit: Some(time_limit),
+            ..self
+        }
+    }
+
+    /// Extract a single rooted term.
+    ///
+    /// This is just a shortcut for [`DagExtractor::solve_multiple`].
+    pub fn solve(&mut self, root: Id) -> RecExpr<L> {
+        self.solve_multiple(&[root]).0
+    }
+
+    /// Extract (potentially multiple) roots, returning a [`RecExpr`]
+    /// containing all of them and their indices in it.
+    ///
+    /// Panics if one of the roots doesn't represent any finite term.
+    pub fn solve_multiple(&mut self, roots: &[Id]) -> (RecExpr<L>, Vec<Id>) {
+        let roots: Vec<Id> = roots.iter().map(|&id| self.egraph.find(id)).collect();
+        let greedy = self.greedy();
+
+        let mut choices: HashMap<Id, usize> = HashMap::default();
+        let mut todo = roots.clone();
+        while let Some(id) = todo.pop() {
+            if choices.contains_key(&id) {
+                continue;
+            }
+            let i = *greedy
+                .get(&id)
+                .unwrap_or_else(|| panic!("Can't extract, eclass {} has no finite term", id));
+            choices.insert(id, i);
+            todo.extend(self.egraph[id].nodes[i].children());
+        }
+
+        if let Some(time_limit) = self.time_limit {
+            let mut search = BranchAndBound {
+                egraph: self.egraph,
+                node_costs: &self.node_costs,
+                deadline: Instant::now() + time_limit,
+                best_cost: self.dag_cost(&choices),
+                best: choices,
+                chosen: HashMap::default(),
+            };
+            search.search(roots.clone(), 0.0);
+            choices = search.best;
+        }
+
+        let egraph = self.egraph;
+        let (expr, root_idxs) = crate::extract::build_multi_root_expr(egraph, &roots, |id| {
+            &egraph[id].nodes[choices[&id]]
+        });
+        assert!(
+            expr.is_dag(),
+            "DagExtractor found a cyclic term!: {:?}",
+            expr
+        );
+        (expr, root_idxs)
+    }
+
+    fn dag_cost(&self, choices: &HashMap<Id, usize>) -> f64 {
+        choices.iter().map(|(id, &i)| self.node_costs[id][i]).sum()
+    }
+
+    /// Picks an e-node for each e-class, in order of increasing cost of
+    /// their sub-DAGs like Dijkstra's algorithm, so the choices never form
+    /// a cycle.
+    fn greedy(&self) -> HashMap<Id, usize> {
+        let mut best: HashMap<Id, usize> = HashMap::default();
+        let mut queue = BinaryHeap::new();
+        let mut queued: HashSet<(Id, usize)> = HashSet::default();
+        for class in self.egraph.classes() {
+            self.queue_ready_nodes(class.id, &best, &mut queued, &mut queue);
+        }
+
+        while let Some(Reverse(entry)) = queue.pop() {
+            let (id, i) = entry.node;
+            if best.contains_key(&id) {
+                continue;
+            }
+            best.insert(id, i);
+            for (_, parent) in &self.egraph[id].parents {
+                let parent = self.egraph.find(*parent);
+                self.queue_ready_nodes(parent, &best, &mut queued, &mut queue);
+            }
+        }
+        best
+    }
+
+    /// Queues the e-nodes of the given e-class whose children all have
+    /// been picked.
+    fn queue_ready_nodes(
+        &self,
+        id: Id,
+        best: &HashMap<Id, usize>,
+        queued: &mut HashSet<(Id, usize)>,
+        queue: &mut BinaryHeap<Reverse<GreedyEntry>>,
+    ) {
+        if best.contains_key(&id) {
+            return;
+        }
+        for (i, node) in self.egraph[id].iter().enumerate() {
+            let children_done = node.all(|child| best.contains_key(&self.egraph.find(child)));
+            if !children_done || !queued.insert((id, i)) {
+                continue;
+            }
+            queue.push(Reverse(GreedyEntry {
+                cost: self.sub_dag_cost(id, i, best),
+                node: (id, i),
+            }));
+        }
+    }
+
+    /// The cost of the sub-DAG of the `i`th e-node of the given e-class,
+    /// like [`dag_cost`](DagExtractor::dag_cost) of the choices it needs.
+    fn sub_dag_cost(&self, id: Id, i: usize, best: &HashMap<Id, usize>) -> f64 {
+        let mut cost = self.node_costs[&id][i];
+        let mut seen: HashSet<Id> = HashSet::default();
+        let mut todo = self.egraph[id].nodes[i].children().to_vec();
+        while let Some(child) = todo.pop() {
+            let child = self.egraph.find(child);
+            if seen.insert(child) {
+                let j = best[&child];
+                cost += self.node_costs[&child][j];
+                todo.extend(self.egraph[child].nodes[j].children());
+            }
+        }
+        cost
+    }
+}
+
+/// An e-node waiting to be picked by the greedy extraction.
+struct GreedyEntry {
+    cost: f64,
+    node: (Id, usize),
+}
+
+impl PartialEq for GreedyEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for GreedyEntry {}
+
+impl PartialOrd for GreedyEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for GreedyEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let cost = self.cost.partial_cmp(&other.cost).unwrap();
+        cost.then_with(|| self.node.cmp(&other.node))
+    }
+}
+
+/// An exact search over the choice of e-node for each needed e-class.
+struct BranchAndBound<'a, L: Language, N: Analysis<L>> {
+    egraph: &'a EGraph<L, N>,
+    node_costs: &'a HashMap<Id, Vec<f64>>,
+    deadline: Instant,
+    best_cost: f64,
+    best: HashMap<Id, usize>,
+    chosen: HashMap<Id, usize>,
+}
+
+impl<'a, L: Language, N: Analysis<L>> BranchAndBound<'a, L, N> {
+    /// Chooses e-nodes for the e-classes in `todo` (and everything they
+    /// need), given that the already chosen e-nodes cost `cost`.
+    fn search(&mut self, mut todo: Vec<Id>, cost: f64) {
+        if Instant::now() > self.deadline {
+            return;
+        }
+
+        let id = loop {
+            match todo.pop() {
+                Some(id) if self.chosen.contains_key(&id) => continue,
+                Some(id) => break id,
+                None => {
+                    if cost < self.best_cost {
+                        self.best_cost = cost;
+                        self.best = self.chosen.clone();
+                    }
+                    return;
+                }
+            }
+        };
+
+        // every e-class still to do costs at least its cheapest e-node
+        let mut needed: HashSet<Id> = todo
+            .iter()
+            .copied()
+            .filter(|id| !self.chosen.contains_key(id))
+            .collect();
+        needed.insert(id);
+        let min_cost = |id: &Id| {
+            self.node_costs[id]
+                .iter()
+                .copied()
+                .fold(f64::INFINITY, f64::min)
+        };
+        let lower_bound: f64 = cost + needed.iter().map(min_cost).sum::<f64>();
+        if lower_bound >= self.best_cost {
+            return;
+        }
+
+        let mut nodes: Vec<usize> = (0..self.node_costs[&id].len()).collect();
+        nodes.sort_by(|&a, &b| {
+            let costs = &self.node_costs[&id];
+            costs[a].partial_cmp(&costs[b]).unwrap()
+        });
+        for i in nodes {
+            let node = &self.egraph[id].nodes[i];
+            let children: Vec<Id> = node
+                .children()
+                .iter()
+                .map(|&c| self.egraph.find(c))
+                .collect();
+            if children.iter().any(|&child| self.reaches(child, id)) {
+                continue;
+            }
+            self.chosen.insert(id, i);
+            let mut todo = todo.clone();
+            todo.extend(children);
+            self.search(todo, cost + self.node_costs[&id][i]);
+            self.chosen.remove(&id);
+        }
+    }
+
+    /// Whether `to` can be reached from `from` through the chosen e-nodes.
+    fn reaches(&self, from: Id, to: Id) -> bool {
+        let mut seen = HashSet::default();
+        let mut todo = vec![from];
+        while let Some(id) = todo.pop() {
+            if id == to {
+                return true;
+            }
+            if let Some(&i) = self.chosen.get(&id) {
+                if seen.insert(id) {
+                    let node = &self.egraph[id].nodes[i];
+                    todo.extend(node.children().iter().map(|&c| self.egraph.find(c)));
+                }
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{SymbolLang as S, *};
+
+    struct OpCost;
+    impl LpCostFunction<S, ()> for OpCost {
+        fn node_cost(&mut self, _egraph: &EGraph<S, ()>, _eclass: Id, enode: &S) -> f64 {
+            match enode.op.as_str() {
+                "c" => 10.0,
+                "d" => 9.0,
+                _ => 1.0,
+            }
+        }
+    }
+
+    #[test]
+    fn dag_extract_sharing() {
+        let mut egraph = EGraph::<S, ()>::default();
+        let a1 = egraph.add_expr(&"(a1 c)".parse().unwrap());
+        let a2 = egraph.add_expr(&"(a2 d)".parse().unwrap());
+        egraph.union(a1, a2);
+        let root = egraph.add_expr(&"(h (a1 c) (b c))".parse().unwrap());
+        egraph.rebuild();
+
+        // on its own, (a2 d) is cheaper, but it can't share c with (b c)
+        let greedy = DagExtractor::new(&egraph, OpCost).solve(root);
+        assert_eq!(greedy.to_string(), "(h (a2 d) (b c))");
+
+        let exact = DagExtractor::new(&egraph, OpCost)
+            .with_branch_and_bound(Duration::from_secs(10))
+            .solve(root);
+        assert_eq!(exact.to_string(), "(h (a1 c) (b c))");
+        assert_eq!(exact.as_ref().len(), 4);
+    }
+
+    #[test]
+    fn dag_extract_cycles() {
+        let mut egraph = EGraph::<S, ()>::default();
+        let x = egraph.add_expr(&"x".parse().unwrap());
+        let fx = egraph.add_expr(&"(f x)".parse().unwrap());
+        let g = egraph.add_expr(&"(g (f x) (f x))".parse().unwrap());
+        egraph.union(x, fx);
+        egraph.union(x, g);
+        let root = egraph.add_expr(&"(h x)".parse().unwrap());
+        egraph.rebuild();
+
+        for time_limit in [None, Some(Duration::from_secs(10))] {
+            let mut extractor = DagExtractor::new(&egraph, AstSize);
+            if let Some(time_limit) = time_limit {
+                extractor = extractor.with_branch_and_bound(time_limit);
+            }
+            let (expr, roots) = extractor.solve_multiple(&[root, x]);
+            assert_eq!(expr.to_string(), "(h x)");
+            assert_eq!(roots, vec![Id::from(1), Id::from(0)]);
+        }
+    }
+}
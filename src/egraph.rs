@@ -84,6 +84,59 @@ pub struct EGraph<L: Language, N: Analysis<L>> {
     /// Only manually set it if you know what you're doing.
     #[cfg_attr(feature = "serde-1", serde(skip))]
     pub clean: bool,
+    /// Undo logs for the checkpoints made by [`EGraph::push`].
+    #[cfg_attr(feature = "serde-1", serde(skip))]
+    #[cfg_attr(feature = "serde-1", serde(default = "Vec::new"))]
+    checkpoints: Vec<Checkpoint<L, N::Data>>,
+    /// The current generation, see [`EGraph::new_generation`].
+    #[cfg_attr(feature = "serde-1", serde(skip))]
+    generation: usize,
+    /// The e-classes touched by adds, unions, and rebuilds since
+    /// `oldest_tracked_generation`, in order.
+    /// The `Id`s are not necessarily canonical.
+    #[cfg_attr(feature = "serde-1", serde(skip))]
+    dirty: Vec<Id>,
+    /// The index into `dirty` where each generation starts, from
+    /// `oldest_tracked_generation` on.
+    /// Empty until [`EGraph::new_generation`] is first called, in which case
+    /// nothing is recorded.
+    #[cfg_attr(feature = "serde-1", serde(skip))]
+    generation_starts: Vec<usize>,
+    /// The oldest generation whose changes are all in `dirty`.
+    #[cfg_attr(feature = "serde-1", serde(skip))]
+    oldest_tracked_generation: usize,
+    /// Set by the [`Runner`] during each iteration, see [`EGraph::is_cancelled`].
+    #[cfg_attr(feature = "serde-1", serde(skip))]
+    pub(crate) cancellation_token: Option<CancellationToken>,
+}
+
+/// An approximate breakdown of the memory used by an [`EGraph`] in bytes,
+/// see [`EGraph::approx_memory_usage`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MemoryUsage {
+    /// The hashcons from enodes to e-class ids.
+    pub memo: usize,
+    /// The e-classes, including their enodes and analysis data,
+    /// but not their parents.
+    pub classes: usize,
+    /// The parent lists of the e-classes.
+    pub parents: usize,
+    /// The proof forest used for explanations, if they are enabled.
+    pub explain: usize,
+    /// The memo of uncanonical enodes used for explanations,
+    /// if they are enabled.
+    pub uncanon_memo: usize,
+    /// Everything else: the union-find, the rebuilding worklists,
+    /// and the indices used for searching.
+    pub other: usize,
+}
+
+impl MemoryUsage {
+    /// The sum of all the parts.
+    pub fn total(&self) -> usize {
+        self.memo + self.classes + self.parents + self.explain + self.uncanon_memo + self.other
+    }
 }
 
 #[cfg(feature = "serde-1")]
@@ -91,6 +144,40 @@ fn default_classes_by_op<K>() -> HashMap<K, HashSet<Id>> {
     HashMap::default()
 }
 
+/// The undo log of a single checkpoint made by [`EGraph::push`].
+///
+/// Instead of copying the e-graph, a checkpoint remembers the original
+/// version of every e-class and memo entry the first time it is modified.
+#[derive(Clone)]
+struct Checkpoint<L: Language, D> {
+    n_ids: usize,
+    clean: bool,
+    pending: Vec<(L, Id)>,
+    analysis_pending: IndexSet<(L, Id)>,
+    /// Roots that became children in the union-find.
+    unions: Vec<Id>,
+    classes: HashMap<Id, Option<EClass<L, D>>>,
+    memo: HashMap<L, Option<Id>>,
+    classes_by_op: Option<HashMap<std::mem::Discriminant<L>, HashSet<Id>>>,
+    // push requires `D: Clone`, so it squirrels away the clone function here
+    clone_class: fn(&EClass<L, D>) -> EClass<L, D>,
+}
+
+impl<L: Language, D> Checkpoint<L, D> {
+    fn save_class(&mut self, id: Id, class: Option<&EClass<L, D>>) {
+        let clone_class = self.clone_class;
+        self.classes
+            .entry(id)
+            .or_insert_with(|| class.map(clone_class));
+    }
+
+    fn save_memo(&mut self, node: &L, old: Option<Id>) {
+        if !self.memo.contains_key(node) {
+            self.memo.insert(node.clone(), old);
+        }
+    }
+}
+
 impl<L: Language, N: Analysis<L> + Default> Default for EGraph<L, N> {
     fn default() -> Self {
         Self::new(N::default())
@@ -120,6 +207,12 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
             memo: Default::default(),
             analysis_pending: Default::default(),
             classes_by_op: Default::default(),
+            checkpoints: Default::default(),
+            generation: 0,
+            dirty: Default::default(),
+            generation_starts: Default::default(),
+            oldest_tracked_generation: 0,
+            cancellation_token: None,
         }
     }
 
@@ -129,7 +222,19 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
     }
 
     /// Returns an mutating iterator over the eclasses in the egraph.
+    ///
+    /// If there is an active checkpoint (see [`EGraph::push`]),
+    /// this has to save every eclass, so prefer indexing
+    /// into the egraph instead.
     pub fn classes_mut(&mut self) -> impl ExactSizeIterator<Item = &mut EClass<L, N::Data>> {
+        if let Some(checkpoint) = self.checkpoints.last_mut() {
+            for (&id, class) in &self.classes {
+                checkpoint.save_class(id, Some(class));
+            }
+        }
+        if self.is_tracking_changes() {
+            self.dirty.extend(self.classes.keys());
+        }
         self.classes.values_mut()
     }
 
@@ -176,6 +281,82 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
         self.classes.len()
     }
 
+    /// Estimates how much memory this egraph uses, in bytes.
+    ///
+    /// This counts the space allocated for the egraph's tables and the
+    /// enodes in them, including children stored outside of the enode
+    /// (like the `Vec` in [`SymbolLang`]).
+    /// It doesn't count anything else the enodes or the analysis data
+    /// point to, or the checkpoints made by [`EGraph::push`].
+    /// This iterates over the whole egraph, so it takes time linear in
+    /// its size.
+    ///
+    /// ```
+    /// use egg::*;
+    /// let mut egraph = EGraph::<SymbolLang, ()>::default().with_explanations_enabled();
+    /// let before = egraph.approx_memory_usage();
+    /// egraph.add_expr(&"(f (g x) (h y))".parse().unwrap());
+    /// let after = egraph.approx_memory_usage();
+    ///
+    /// assert!(after.total() > before.total());
+    /// assert!(after.memo > 0 && after.classes > 0 && after.parents > 0);
+    /// assert!(after.explain > 0 && after.uncanon_memo > 0);
+    /// ```
+    pub fn approx_memory_usage(&self) -> MemoryUsage {
+        use std::mem::size_of;
+        let nodes_size = |nodes: &[L], capacity: usize| {
+            capacity * size_of::<L>() + nodes.iter().map(children_heap_size).sum::<usize>()
+        };
+
+        let memo = hash_table_size::<(L, Id)>(self.memo.capacity())
+            + self.memo.keys().map(children_heap_size).sum::<usize>();
+
+        let mut classes = hash_table_size::<(Id, EClass<L, N::Data>)>(self.classes.capacity());
+        let mut parents = 0;
+        for class in self.classes.values() {
+            classes += nodes_size(&class.nodes, class.nodes.capacity());
+            parents += class.parents.capacity() * size_of::<(L, Id)>()
+                + class
+                    .parents
+                    .iter()
+                    .map(|(node, _)| children_heap_size(node))
+                    .sum::<usize>();
+        }
+
+        let (explain, uncanon_memo) = match &self.explain {
+            Some(explain) => explain.approx_memory_usage(),
+            None => (0, 0),
+        };
+
+        let classes_by_op = hash_table_size::<(std::mem::Discriminant<L>, HashSet<Id>)>(
+            self.classes_by_op.capacity(),
+        ) + self
+            .classes_by_op
+            .values()
+            .map(|ids| hash_table_size::<Id>(ids.capacity()))
+            .sum::<usize>();
+        let other = self.unionfind.size() * size_of::<Id>()
+            + self.pending.capacity() * size_of::<(L, Id)>()
+            + self
+                .pending
+                .iter()
+                .map(|(node, _)| children_heap_size(node))
+                .sum::<usize>()
+            + hash_table_size::<(L, Id)>(self.analysis_pending.capacity())
+            + classes_by_op
+            + self.dirty.capacity() * size_of::<Id>()
+            + self.generation_starts.capacity() * size_of::<usize>();
+
+        MemoryUsage {
+            memo,
+            classes,
+            parents,
+            explain,
+            uncanon_memo,
+            other,
+        }
+    }
+
     /// Enable explanations for this `EGraph`.
     /// This allows the egraph to explain why two expressions are
     /// equivalent with the [`explain_equivalence`](EGraph::explain_equivalence) function.
@@ -196,11 +377,48 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
         self
     }
 
+    /// Make [`explain_equivalence`](EGraph::explain_equivalence) search for
+    /// a short explanation instead of following the order in which things
+    /// were unioned, which can give much longer explanations.
+    /// This looks for the shortest path between the two terms in the graph
+    /// of the unions and congruences between the e-nodes, so it's slower.
+    /// Explanations must already be enabled.
+    pub fn with_explanation_length_optimization(mut self) -> Self {
+        if let Some(explain) = &mut self.explain {
+            explain.optimize_explanation_lengths = true;
+        } else {
+            panic!("Need to set explanations enabled before optimizing explanation lengths.");
+        }
+        self
+    }
+
+    /// Go back to explanations that follow the order of the unions,
+    /// see [`with_explanation_length_optimization`](EGraph::with_explanation_length_optimization).
+    pub fn without_explanation_length_optimization(mut self) -> Self {
+        if let Some(explain) = &mut self.explain {
+            explain.optimize_explanation_lengths = false;
+        }
+        self
+    }
+
     /// Check if explanations are enabled.
     pub fn are_explanations_enabled(&self) -> bool {
         self.explain.is_some()
     }
 
+    /// Whether the [`Runner`] that is running on this e-graph has been
+    /// cancelled (see [`Runner::with_cancellation_token`]).
+    /// Always `false` outside of a [`Runner`] iteration.
+    ///
+    /// [`Pattern`]s and the default [`Searcher`] and [`Applier`] methods
+    /// check this between e-classes and stop early when it is set, which
+    /// custom implementations doing a lot of work should do as well.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation_token
+            .as_ref()
+            .map_or(false, CancellationToken::is_cancelled)
+    }
+
     /// When explanations are enabled, this function
     /// produces an [`Explanation`] describing why two expressions are equivalent.
     ///
@@ -287,8 +505,181 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
 
     /// This is private, but internals should use this whenever
     /// possible because it does path compression.
+    ///
+    /// Path compression is turned off while there is an active checkpoint,
+    /// so the only changes to the union-find are the unions themselves.
     fn find_mut(&mut self, id: Id) -> Id {
-        self.unionfind.find_mut(id)
+        if self.checkpoints.is_empty() {
+            self.unionfind.find_mut(id)
+        } else {
+            self.unionfind.find(id)
+        }
+    }
+
+    /// Records `id` in the current checkpoint (if any) before it is modified.
+    fn save_class(&mut self, id: Id) {
+        if let Some(checkpoint) = self.checkpoints.last_mut() {
+            checkpoint.save_class(id, self.classes.get(&id));
+        }
+    }
+
+    /// Makes `root2` a child of `root1` in the union-find,
+    /// recording it in the current checkpoint (if any).
+    fn union_roots(&mut self, root1: Id, root2: Id) {
+        if let Some(checkpoint) = self.checkpoints.last_mut() {
+            checkpoint.unions.push(root2);
+        }
+        self.unionfind.union(root1, root2);
+    }
+
+    /// Whether the changed e-classes are recorded,
+    /// see [`new_generation`](EGraph::new_generation).
+    fn is_tracking_changes(&self) -> bool {
+        !self.generation_starts.is_empty()
+    }
+
+    /// Records that the e-class `id` changed in the current generation.
+    fn touch(&mut self, id: Id) {
+        if self.is_tracking_changes() && self.dirty.last() != Some(&id) {
+            self.dirty.push(id);
+        }
+    }
+
+    /// Returns the current generation of this e-graph.
+    ///
+    /// Once [`new_generation`](EGraph::new_generation) has been called,
+    /// the e-graph records which e-classes are touched by
+    /// [`add`](EGraph::add), [`union`](EGraph::union), and
+    /// [`rebuild`](EGraph::rebuild), tagging each change with the current
+    /// generation.
+    /// See [`classes_changed_since`](EGraph::classes_changed_since).
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Starts a new generation and returns it.
+    ///
+    /// Changes made from now on are tagged with the new generation, so a
+    /// search done right after this call has seen everything tagged with
+    /// an earlier one.
+    /// The first call starts recording the changes, which keeps using memory
+    /// until [`forget_generations_before`](EGraph::forget_generations_before)
+    /// is called.
+    /// [`Runner`] calls this before searching in each iteration when
+    /// [incremental search](Runner::with_incremental_search) is enabled.
+    pub fn new_generation(&mut self) -> usize {
+        if let Some(&start) = self.generation_starts.last() {
+            let mut touched = self.dirty.split_off(start);
+            touched.sort_unstable();
+            touched.dedup();
+            self.dirty.extend(touched);
+        } else {
+            self.oldest_tracked_generation = self.generation + 1;
+        }
+        self.generation += 1;
+        self.generation_starts.push(self.dirty.len());
+        self.generation
+    }
+
+    /// Stops keeping track of the changes made before `generation`,
+    /// freeing the memory they use.
+    ///
+    /// After this, [`classes_changed_since`](EGraph::classes_changed_since)
+    /// returns every e-class for an earlier generation.
+    /// [`Runner`] calls this after each iteration with the oldest
+    /// generation that a rule still needs.
+    pub fn forget_generations_before(&mut self, generation: usize) {
+        let generation = generation.min(self.generation);
+        if !self.is_tracking_changes() || generation <= self.oldest_tracked_generation {
+            return;
+        }
+        let forgotten = generation - self.oldest_tracked_generation;
+        let start = self.generation_starts[forgotten];
+        self.dirty.drain(..start);
+        self.generation_starts.drain(..forgotten);
+        self.generation_starts.iter_mut().for_each(|s| *s -= start);
+        self.oldest_tracked_generation = generation;
+    }
+
+    /// Returns the canonical e-classes that were touched in generation
+    /// `since` or later, along with their ancestors up to `depth` levels up.
+    ///
+    /// A pattern whose e-nodes are nested at most `depth + 1` deep only looks
+    /// at e-classes `depth` levels below the e-class it matches, so any new
+    /// match for it since generation `since` must be rooted in one of these
+    /// e-classes.
+    /// This is what [`Searcher::search_since`] uses to only search the part
+    /// of the e-graph that changed.
+    ///
+    /// If the changes since `since` weren't recorded, because
+    /// [`new_generation`](EGraph::new_generation) wasn't called yet or
+    /// [`forget_generations_before`](EGraph::forget_generations_before) was,
+    /// this returns every e-class.
+    ///
+    /// The e-graph should be [clean](EGraph::clean), otherwise the
+    /// ancestors may be incomplete.
+    /// The result is sorted.
+    ///
+    /// # Example
+    /// ```
+    /// use egg::{*, SymbolLang as S};
+    /// let mut egraph = EGraph::<S, ()>::default();
+    /// let fx = egraph.add_expr(&"(g (f x))".parse().unwrap());
+    /// egraph.rebuild();
+    /// let since = egraph.new_generation();
+    /// assert!(egraph.classes_changed_since(since, 10).is_empty());
+    ///
+    /// let x = egraph.add(S::leaf("x"));
+    /// let y = egraph.add(S::leaf("y"));
+    /// egraph.union(x, y);
+    /// egraph.rebuild();
+    /// assert_eq!(egraph.classes_changed_since(since, 0), vec![egraph.find(x)]);
+    /// assert_eq!(egraph.classes_changed_since(since, 2).len(), 3);
+    /// ```
+    pub fn classes_changed_since(&self, since: usize, depth: usize) -> Vec<Id> {
+        if !self.is_tracking_changes() || since < self.oldest_tracked_generation {
+            let mut all: Vec<Id> = self.classes.keys().copied().collect();
+            all.sort_unstable();
+            return all;
+        }
+        let start = self
+            .generation_starts
+            .get(since - self.oldest_tracked_generation)
+            .copied()
+            .unwrap_or(self.dirty.len());
+        let mut changed: HashSet<Id> = self.dirty[start..]
+            .iter()
+            // ids may be gone after `pop`
+            .filter(|&&id| usize::from(id) < self.unionfind.size())
+            .map(|&id| self.find(id))
+            .filter(|id| self.classes.contains_key(id))
+            .collect();
+
+        let mut frontier: Vec<Id> = changed.iter().copied().collect();
+        for _ in 0..depth {
+            let mut next = vec![];
+            for id in frontier {
+                for (_, parent) in &self[id].parents {
+                    let parent = self.find(*parent);
+                    if changed.insert(parent) {
+                        next.push(parent);
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+
+        let mut changed: Vec<Id> = changed.into_iter().collect();
+        changed.sort_unstable();
+        changed
+    }
+
+    /// Returns the number of active checkpoints made by [`EGraph::push`].
+    pub fn n_checkpoints(&self) -> usize {
+        self.checkpoints.len()
     }
 
     /// Creates a [`Dot`] to visualize this egraph. See [`Dot`].
@@ -318,6 +709,8 @@ impl<L: Language, N: Analysis<L>> std::ops::Index<Id> for EGraph<L, N> {
 impl<L: Language, N: Analysis<L>> std::ops::IndexMut<Id> for EGraph<L, N> {
     fn index_mut(&mut self, id: Id) -> &mut Self::Output {
         let id = self.find_mut(id);
+        self.save_class(id);
+        self.touch(id);
         self.classes
             .get_mut(&id)
             .unwrap_or_else(|| panic!("Invalid id {}", id))
@@ -378,7 +771,16 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
         self.find(id)
     }
 
-    fn add_instantiation_internal(&mut self, pat: &PatternAst<L>, subst: &Subst) -> Id {
+    /// Like [`add_instantiation`](EGraph::add_instantiation), but the
+    /// returned id is not canonical.
+    ///
+    /// When explanations are enabled, the id stands for exactly the
+    /// instantiated enode, see [`add_uncanonical`](EGraph::add_uncanonical).
+    pub fn add_instantiation_noncanonical(&mut self, pat: &PatternAst<L>, subst: &Subst) -> Id {
+        self.add_instantiation_internal(pat, subst)
+    }
+
+    pub(crate) fn add_instantiation_internal(&mut self, pat: &PatternAst<L>, subst: &Subst) -> Id {
         let nodes = pat.as_ref();
         let mut new_ids = Vec::with_capacity(nodes.len());
         let mut new_node_q = Vec::with_capacity(nodes.len());
@@ -491,6 +893,16 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
         self.find(id)
     }
 
+    /// Like [`add`](EGraph::add), but the returned id is not canonical.
+    ///
+    /// When explanations are enabled, the id stands for exactly this enode,
+    /// even if it's in a bigger eclass, so it can be given to
+    /// [`union_by_analysis`](EGraph::union_by_analysis) or
+    /// [`union_with_reason`](EGraph::union_with_reason).
+    pub fn add_uncanonical(&mut self, enode: L) -> Id {
+        self.add_internal(enode)
+    }
+
     /// Adds an enode to the egraph and also returns the the enode's id (uncanonicalized).
     fn add_internal(&mut self, mut enode: L) -> Id {
         let original = enode.clone();
@@ -503,8 +915,8 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
                 } else {
                     let new_id = self.unionfind.make_set();
                     explain.add(original, new_id, new_id);
-                    self.unionfind.union(id, new_id);
                     explain.union(existing_id, new_id, Justification::Congruence, true);
+                    self.union_roots(id, new_id);
                     new_id
                 }
             } else {
@@ -537,14 +949,21 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
         // add this enode to the parent lists of its children
         enode.for_each(|child| {
             let tup = (enode.clone(), id);
-            self[child].parents.push(tup);
+            let child = self.find_mut(child);
+            self.save_class(child);
+            self.classes.get_mut(&child).unwrap().parents.push(tup);
         });
 
         // TODO is this needed?
         self.pending.push((enode.clone(), id));
 
+        if let Some(checkpoint) = self.checkpoints.last_mut() {
+            checkpoint.save_class(id, None);
+            checkpoint.save_memo(&enode, None);
+        }
         self.classes.insert(id, class);
         assert!(self.memo.insert(enode, id).is_none());
+        self.touch(id);
 
         id
     }
@@ -604,6 +1023,55 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
         (self.find(id1), did_union)
     }
 
+    /// Like [`union_instantiations`](EGraph::union_instantiations), but from
+    /// an e-node that was already added, and for a rule that also needed
+    /// the given premises: pairs of an e-class bound by the rule and a term
+    /// it matched in that e-class.
+    /// Used by [`MultiPattern`]s.
+    ///
+    /// Returns the id of the instantiation of `to_pat`, along with
+    /// a `bool` indicating whether a union occured.
+    pub(crate) fn union_instantiation_with_premises(
+        &mut self,
+        from: Id,
+        to_pat: &PatternAst<L>,
+        subst: &Subst,
+        rule_name: Symbol,
+        premises: Vec<(Id, Id)>,
+    ) -> (Id, bool) {
+        let size_before = self.unionfind.size();
+        let to = self.add_instantiation_internal(to_pat, subst);
+        let rhs_new = self.unionfind.size() > size_before;
+
+        let did_union = self.perform_union(
+            from,
+            to,
+            Some(Justification::RuleWithPremises(rule_name, premises)),
+            rhs_new,
+        );
+        (to, did_union)
+    }
+
+    /// Unions two eclasses from [`Analysis::modify`], e.g. to add the value
+    /// found by constant folding to an eclass.
+    ///
+    /// Unlike [`union`](EGraph::union), this can be used when explanations are enabled.
+    /// Explanations then show the union as a rewrite from the enode `id1` to the
+    /// enode `id2` with the given reason, marked as
+    /// [made by an analysis](crate::RewriteKind::Analysis).
+    /// So `id1` and `id2` must be the exact enodes the reason is about, as returned by
+    /// [`add_uncanonical`](EGraph::add_uncanonical) or
+    /// [`add_instantiation_noncanonical`](EGraph::add_instantiation_noncanonical).
+    /// A canonical id, like the eclass passed to [`Analysis::modify`] or the one returned
+    /// by [`add`](EGraph::add), stands for whichever enode created that eclass,
+    /// which may have different children.
+    ///
+    /// The returned `bool` indicates whether a union is necessary,
+    /// so it's `false` if they were already equivalent.
+    pub fn union_by_analysis(&mut self, id1: Id, id2: Id, reason: impl Into<Symbol>) -> bool {
+        self.union_justified(id1, id2, reason, Justification::Analysis)
+    }
+
     /// Unions two eclasses given their ids.
     ///
     /// The given ids need not be canonical.
@@ -611,18 +1079,67 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
     /// so it's `false` if they were already equivalent.
     ///
     /// When explanations are enabled, this function is not available.
-    /// Instead, use [`union_instantiations`](EGraph::union_instantiations).
+    /// Instead, use [`union_instantiations`](EGraph::union_instantiations)
+    /// or [`union_with_reason`](EGraph::union_with_reason).
     /// See [`explain_equivalence`](Runner::explain_equivalence) for a more detailed
     /// explanation of the feature.
     ///
     ///
     pub fn union(&mut self, id1: Id, id2: Id) -> bool {
         if self.explain.is_some() {
-            panic!("Use union_instantiations when explanation mode is enabled.");
+            panic!(
+                "Use union_instantiations or union_with_reason when explanation mode is enabled."
+            );
         }
         self.perform_union(id1, id2, None, false)
     }
 
+    /// Unions two eclasses given their ids, because of a fact that egg can't
+    /// check, e.g. the result of a type checker or an annotation from the user.
+    ///
+    /// Explanations show the union as an [axiom](crate::RewriteKind::Axiom)
+    /// rewriting the enode `id1` to the enode `id2`, with the given reason.
+    /// Like for [`union_by_analysis`](EGraph::union_by_analysis), these should be the ids of
+    /// the exact enodes, e.g. from [`add_uncanonical`](EGraph::add_uncanonical).
+    ///
+    /// The returned `bool` indicates whether a union is necessary,
+    /// so it's `false` if they were already equivalent.
+    ///
+    /// ```
+    /// use egg::*;
+    /// let mut egraph = EGraph::<SymbolLang, ()>::default().with_explanations_enabled();
+    /// let x = egraph.add_uncanonical(SymbolLang::leaf("x"));
+    /// let fx = egraph.add_uncanonical(SymbolLang::new("f", vec![x]));
+    /// let y = egraph.add_uncanonical(SymbolLang::leaf("y"));
+    /// let gy = egraph.add_uncanonical(SymbolLang::new("g", vec![y]));
+    /// egraph.union_with_reason(fx, gy, "type-checker");
+    /// egraph.rebuild();
+    ///
+    /// let mut explanation =
+    ///     egraph.explain_equivalence(&"(f x)".parse().unwrap(), &"(g y)".parse().unwrap());
+    /// assert_eq!(
+    ///     explanation.get_flat_strings(),
+    ///     vec!["(f x)", "(Axiom=> type-checker (g y))"]
+    /// );
+    /// ```
+    pub fn union_with_reason(&mut self, id1: Id, id2: Id, reason: impl Into<Symbol>) -> bool {
+        self.union_justified(id1, id2, reason, Justification::Axiom)
+    }
+
+    /// Unions two enodes, justified by `make` applied to the reason
+    /// when explanations are enabled.
+    fn union_justified(
+        &mut self,
+        id1: Id,
+        id2: Id,
+        reason: impl Into<Symbol>,
+        make: impl FnOnce(Symbol) -> Justification,
+    ) -> bool {
+        // only intern the reason if it's needed
+        let justification = self.explain.as_ref().map(|_| make(reason.into()));
+        self.perform_union(id1, id2, justification, false)
+    }
+
     fn perform_union(
         &mut self,
         enode_id1: Id,
@@ -650,9 +1167,12 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
         }
 
         // make id1 the new root
-        self.unionfind.union(id1, id2);
+        self.union_roots(id1, id2);
 
         assert_ne!(id1, id2);
+        self.save_class(id1);
+        self.save_class(id2);
+        self.touch(id1);
         let class2 = self.classes.remove(&id2).unwrap();
         let class1 = self.classes.get_mut(&id1).unwrap();
         assert_eq!(id1, class1.id);
@@ -718,21 +1238,51 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
     #[inline(never)]
     fn rebuild_classes(&mut self) -> usize {
         let mut classes_by_op = std::mem::take(&mut self.classes_by_op);
+        if let Some(checkpoint) = self.checkpoints.last_mut() {
+            if checkpoint.classes_by_op.is_none() {
+                checkpoint.classes_by_op = Some(classes_by_op.clone());
+            }
+        }
         classes_by_op.values_mut().for_each(|ids| ids.clear());
 
         let mut trimmed = 0;
+        let tracking = self.is_tracking_changes();
         let uf = &mut self.unionfind;
+        let mut checkpoint = self.checkpoints.last_mut();
+        let dirty = &mut self.dirty;
 
         for class in self.classes.values_mut() {
             let old_len = class.len();
-            class
-                .nodes
-                .iter_mut()
-                .for_each(|n| n.update_children(|id| uf.find_mut(id)));
+            let mut changed = false;
+            if let Some(checkpoint) = checkpoint.as_mut() {
+                let is_canonical = class.nodes.windows(2).all(|w| w[0] < w[1])
+                    && class.nodes.iter().all(|n| n.all(|id| uf.find(id) == id));
+                if !is_canonical {
+                    checkpoint.save_class(class.id, Some(class));
+                }
+                class.nodes.iter_mut().for_each(|n| {
+                    n.update_children(|id| {
+                        let root = uf.find(id);
+                        changed |= root != id;
+                        root
+                    })
+                });
+            } else {
+                class.nodes.iter_mut().for_each(|n| {
+                    n.update_children(|id| {
+                        let root = uf.find_mut(id);
+                        changed |= root != id;
+                        root
+                    })
+                });
+            }
             class.nodes.sort_unstable();
             class.nodes.dedup();
 
             trimmed += old_len - class.nodes.len();
+            if changed && tracking {
+                dirty.push(class.id);
+            }
 
             let mut add = |n: &L| {
                 #[allow(enum_intrinsics_non_enums)]
@@ -807,6 +1357,9 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
         while !self.pending.is_empty() || !self.analysis_pending.is_empty() {
             while let Some((mut node, class)) = self.pending.pop() {
                 node.update_children(|id| self.find_mut(id));
+                if let Some(checkpoint) = self.checkpoints.last_mut() {
+                    checkpoint.save_memo(&node, self.memo.get(&node).copied());
+                }
                 if let Some(memo_class) = self.memo.insert(node, class) {
                     let did_something = self.perform_union(
                         memo_class,
@@ -821,11 +1374,13 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
             while let Some((node, class_id)) = self.analysis_pending.pop() {
                 let class_id = self.find_mut(class_id);
                 let node_data = N::make(self, &node);
+                self.save_class(class_id);
                 let class = self.classes.get_mut(&class_id).unwrap();
 
                 let did_merge = self.analysis.merge(&mut class.data, node_data);
                 if did_merge.0 {
                     self.analysis_pending.extend(class.parents.iter().cloned());
+                    self.touch(class_id);
                     N::modify(self, class_id)
                 }
             }
@@ -915,6 +1470,230 @@ impl<L: Language, N: Analysis<L>> EGraph<L, N> {
     }
 }
 
+// Garbage collection
+impl<L: Language, N: Analysis<L>> EGraph<L, N> {
+    /// Deletes every e-class that is not reachable from the given `roots`
+    /// by following the children of its e-nodes.
+    ///
+    /// The surviving e-classes are renumbered densely (preserving their
+    /// relative order), so this returns a map from every old [`Id`] whose
+    /// e-class survived to its new [`Id`].
+    /// Old [`Id`]s of deleted e-classes are not in the map.
+    /// Use it to fix up any [`Id`]s you hold on to, including any stored in
+    /// your [`Analysis`] data, which this method does not touch.
+    ///
+    /// The memo, parent lists, and union-find are rebuilt from the
+    /// surviving e-classes, so this takes time linear in the size of the
+    /// e-graph.
+    ///
+    /// Panics if the e-graph is not [`clean`](EGraph::clean), if
+    /// explanations are enabled, or if there is an active checkpoint
+    /// (see [`EGraph::push`]).
+    ///
+    /// # Example
+    /// ```
+    /// use egg::{*, SymbolLang as S};
+    /// let mut egraph = EGraph::<S, ()>::default();
+    /// let junk = egraph.add_expr(&"(f a b)".parse().unwrap());
+    /// let root = egraph.add_expr(&"(g b)".parse().unwrap());
+    /// egraph.rebuild();
+    /// assert_eq!(egraph.number_of_classes(), 4);
+    ///
+    /// let remap = egraph.retain_reachable(&[root]);
+    /// assert_eq!(egraph.number_of_classes(), 2);
+    /// assert!(!remap.contains_key(&junk));
+    /// let root = remap[&root];
+    /// assert_eq!(egraph.lookup_expr(&"(g b)".parse().unwrap()), Some(root));
+    /// assert_eq!(egraph.lookup_expr(&"(f a b)".parse().unwrap()), None);
+    /// ```
+    pub fn retain_reachable(&mut self, roots: &[Id]) -> HashMap<Id, Id> {
+        assert!(self.clean, "Tried to garbage collect a dirty e-graph!");
+        assert!(
+            self.explain.is_none(),
+            "Can't garbage collect an e-graph with explanations enabled"
+        );
+        assert!(
+            self.checkpoints.is_empty(),
+            "Can't garbage collect an e-graph with active checkpoints"
+        );
+
+        let mut reachable: HashSet<Id> = HashSet::default();
+        let mut todo: Vec<Id> = roots.iter().map(|&id| self.find(id)).collect();
+        while let Some(id) = todo.pop() {
+            if reachable.insert(id) {
+                for node in &self[id].nodes {
+                    todo.extend(node.children().iter().map(|&c| self.find(c)));
+                }
+            }
+        }
+
+        let mut kept: Vec<Id> = reachable.into_iter().collect();
+        kept.sort_unstable();
+        let new_ids: HashMap<Id, Id> = kept
+            .iter()
+            .enumerate()
+            .map(|(i, &id)| (id, Id::from(i)))
+            .collect();
+
+        let remap: HashMap<Id, Id> = (0..self.unionfind.size())
+            .filter_map(|i| {
+                let old = Id::from(i);
+                new_ids.get(&self.find(old)).map(|&new| (old, new))
+            })
+            .collect();
+
+        let old_classes = std::mem::take(&mut self.classes);
+        let mut unionfind = UnionFind::default();
+        let mut memo = HashMap::default();
+        for (old_id, mut class) in old_classes {
+            let id = match new_ids.get(&old_id) {
+                Some(&id) => id,
+                None => continue,
+            };
+            class.id = id;
+            for node in &mut class.nodes {
+                node.update_children(|c| remap[&c]);
+                memo.insert(node.clone(), id);
+            }
+            class
+                .parents
+                .retain(|(_, parent)| remap.contains_key(parent));
+            for (node, parent) in &mut class.parents {
+                node.update_children(|c| remap[&c]);
+                *parent = remap[parent];
+            }
+            self.classes.insert(id, class);
+        }
+        for _ in 0..kept.len() {
+            unionfind.make_set();
+        }
+
+        let mut dirty = vec![];
+        let mut generation_starts = vec![];
+        for (i, &start) in self.generation_starts.iter().enumerate() {
+            generation_starts.push(dirty.len());
+            let end = self
+                .generation_starts
+                .get(i + 1)
+                .copied()
+                .unwrap_or(self.dirty.len());
+            dirty.extend(self.dirty[start..end].iter().filter_map(|id| remap.get(id)));
+        }
+
+        self.unionfind = unionfind;
+        self.memo = memo;
+        self.dirty = dirty;
+        self.generation_starts = generation_starts;
+        self.rebuild_classes();
+        debug_assert!(self.check_memo());
+        remap
+    }
+}
+
+// Checkpointing and backtracking
+impl<L: Language, N: Analysis<L>> EGraph<L, N>
+where
+    N::Data: Clone,
+{
+    /// Makes a checkpoint that a later call to [`pop`](EGraph::pop())
+    /// can roll the e-graph back to.
+    ///
+    /// Rather than cloning the whole e-graph, a checkpoint keeps an undo log
+    /// of the adds, unions, analysis data changes and rebuilds that happen
+    /// after it, so backtracking only costs as much as the work done since
+    /// the checkpoint.
+    /// Checkpoints can be nested; each [`pop`](EGraph::pop()) undoes the
+    /// changes since the most recent [`push`](EGraph::push()).
+    /// This works with explanations enabled as well.
+    ///
+    /// Note that the [`Analysis`] itself (the [`EGraph::analysis`] field)
+    /// is not rolled back, only the per-eclass data.
+    /// Also, path compression is turned off while there is an active
+    /// checkpoint.
+    ///
+    /// # Example
+    /// ```
+    /// use egg::{*, SymbolLang as S};
+    /// let mut egraph = EGraph::<S, ()>::default();
+    /// let x = egraph.add(S::leaf("x"));
+    /// let y = egraph.add(S::leaf("y"));
+    /// let fx = egraph.add(S::new("f", vec![x]));
+    /// let fy = egraph.add(S::new("f", vec![y]));
+    /// egraph.rebuild();
+    ///
+    /// egraph.push();
+    /// egraph.union(x, y);
+    /// egraph.add(S::leaf("z"));
+    /// egraph.rebuild();
+    /// assert_eq!(egraph.find(fx), egraph.find(fy));
+    ///
+    /// egraph.pop();
+    /// assert_ne!(egraph.find(fx), egraph.find(fy));
+    /// assert_eq!(egraph.number_of_classes(), 4);
+    /// assert_eq!(egraph.lookup(S::leaf("z")), None);
+    /// ```
+    pub fn push(&mut self) {
+        if let Some(explain) = &mut self.explain {
+            explain.push();
+        }
+        self.checkpoints.push(Checkpoint {
+            n_ids: self.unionfind.size(),
+            clean: self.clean,
+            pending: self.pending.clone(),
+            analysis_pending: self.analysis_pending.clone(),
+            unions: Default::default(),
+            classes: Default::default(),
+            memo: Default::default(),
+            classes_by_op: None,
+            clone_class: EClass::clone,
+        });
+    }
+
+    /// Rolls the e-graph back to the state it was in at the most recent
+    /// [`push`](EGraph::push()).
+    ///
+    /// Panics if there is no active checkpoint.
+    pub fn pop(&mut self) {
+        let checkpoint = self.checkpoints.pop().expect("No checkpoint to pop");
+
+        for (id, class) in checkpoint.classes {
+            match class {
+                Some(class) => {
+                    self.touch(id);
+                    self.classes.insert(id, class)
+                }
+                None => self.classes.remove(&id),
+            };
+        }
+
+        for (node, id) in checkpoint.memo {
+            match id {
+                Some(id) => self.memo.insert(node, id),
+                None => self.memo.remove(&node),
+            };
+        }
+
+        for &id in checkpoint.unions.iter().rev() {
+            if usize::from(id) < checkpoint.n_ids {
+                self.unionfind.make_root(id);
+            }
+        }
+        self.unionfind.truncate(checkpoint.n_ids);
+
+        if let Some(classes_by_op) = checkpoint.classes_by_op {
+            self.classes_by_op = classes_by_op;
+        }
+
+        if let Some(explain) = &mut self.explain {
+            explain.pop();
+        }
+
+        self.pending = checkpoint.pending;
+        self.analysis_pending = checkpoint.analysis_pending;
+        self.clean = checkpoint.clean;
+    }
+}
+
 struct EGraphDump<'a, L: Language, N: Analysis<L>>(&'a EGraph<L, N>);
 
 impl<'a, L: Language, N: Analysis<L>> Debug for EGraphDump<'a, L, N> {
@@ -955,6 +1734,359 @@ mod tests {
         egraph.rebuild();
     }
 
+    #[test]
+    fn generations() {
+        let mut egraph = EGraph::<SymbolLang, ()>::default();
+        egraph.add_expr(&"(f (g x))".parse().unwrap());
+        egraph.rebuild();
+        // nothing is recorded until a generation is started
+        assert!(egraph.dirty.is_empty());
+        assert_eq!(egraph.classes_changed_since(0, 0).len(), 3);
+
+        let first = egraph.new_generation();
+        let y = egraph.add(SymbolLang::leaf("y"));
+        egraph.rebuild();
+        let second = egraph.new_generation();
+        let z = egraph.add(SymbolLang::leaf("z"));
+        egraph.rebuild();
+        assert_eq!(egraph.classes_changed_since(first, 0), vec![y, z]);
+        assert_eq!(egraph.classes_changed_since(second, 0), vec![z]);
+
+        egraph.forget_generations_before(second);
+        assert_eq!(egraph.dirty, vec![z]);
+        assert_eq!(egraph.classes_changed_since(second, 0), vec![z]);
+        assert_eq!(egraph.classes_changed_since(first, 0).len(), 5);
+    }
+
+    #[test]
+    fn explanation_length_optimization() {
+        crate::init_logger();
+        let mut egraph = EGraph::<SymbolLang, ()>::default()
+            .with_explanations_enabled()
+            .with_explanation_length_optimization();
+
+        // (f a) and (f b) are first unioned the long way around,
+        // so the proof forest doesn't know they're congruent
+        let steps = [
+            ("(f a)", "(g a)", "f-to-g"),
+            ("(g a)", "(h a)", "g-to-h"),
+            ("(h a)", "(f b)", "h-to-f"),
+            ("a", "b", "a-to-b"),
+        ];
+        for (from, to, rule) in steps {
+            egraph.union_instantiations(
+                &from.parse().unwrap(),
+                &to.parse().unwrap(),
+                &Default::default(),
+                rule,
+            );
+        }
+        egraph.rebuild();
+
+        let start = "(f a)".parse().unwrap();
+        let goal = "(f b)".parse().unwrap();
+        let mut short = egraph.explain_equivalence(&start, &goal);
+        assert_eq!(short.get_flat_size(), 1);
+        assert_eq!(short.get_tree_size(), 1);
+        assert_eq!(
+            short.get_flat_strings(),
+            vec!["(f a)", "(f (Rewrite=> a-to-b b))"]
+        );
+
+        egraph = egraph.without_explanation_length_optimization();
+        let mut long = egraph.explain_equivalence(&start, &goal);
+        assert_eq!(long.get_flat_size(), 3);
+        assert_eq!(long.get_tree_size(), 3);
+    }
+
+    #[test]
+    fn verify_proof() {
+        use crate::*;
+
+        // a custom applier, so its rewrites can't be checked
+        struct Double;
+        impl Applier<SymbolLang, ()> for Double {
+            fn apply_one(
+                &self,
+                egraph: &mut EGraph<SymbolLang, ()>,
+                _eclass: Id,
+                subst: &Subst,
+                searcher_ast: Option<&PatternAst<SymbolLang>>,
+                rule_name: Symbol,
+            ) -> Vec<Id> {
+                let lhs = searcher_ast.unwrap();
+                let rhs = "(+ ?a ?a)".parse().unwrap();
+                let (id, unioned) = egraph.union_instantiations(lhs, &rhs, subst, rule_name);
+                if unioned {
+                    vec![id]
+                } else {
+                    vec![]
+                }
+            }
+        }
+
+        crate::init_logger();
+        let rules: Vec<Rewrite<SymbolLang, ()>> = vec![
+            rewrite!("comm"; "(+ ?a ?b)" => "(+ ?b ?a)"),
+            rewrite!("double"; "(* ?a 2)" => { Double }),
+        ];
+        let start = "(* (+ x y) 2)".parse().unwrap();
+        let mut runner = Runner::default()
+            .with_explanations_enabled()
+            .with_expr(&start)
+            .run(&rules);
+        runner.egraph.union_instantiations(
+            &"x".parse().unwrap(),
+            &"z".parse().unwrap(),
+            &Default::default(),
+            "x-is-z",
+        );
+        runner.egraph.rebuild();
+
+        let goal = "(+ (+ y z) (+ x y))".parse().unwrap();
+        let mut explanation = runner.explain_equivalence(&start, &goal);
+        let report = explanation.verify_proof(&rules).unwrap();
+        assert_eq!(report.steps, explanation.get_flat_size());
+        assert!(!report.is_fully_checked());
+
+        let flat = explanation.make_flat_explanation().clone();
+        let mut reasons = vec![];
+        for unchecked in &report.unchecked {
+            let mut term = &flat[unchecked.step];
+            for &i in &unchecked.location {
+                term = &term.children[i];
+            }
+            assert_eq!(
+                term.forward_rule.or(term.backward_rule),
+                Some(unchecked.rule)
+            );
+            reasons.push((unchecked.rule.as_str(), unchecked.reason));
+        }
+        reasons.sort_unstable_by_key(|(rule, _)| *rule);
+        reasons.dedup();
+        assert_eq!(
+            reasons,
+            vec![
+                ("double", UncheckedReason::CustomApplier),
+                ("x-is-z", UncheckedReason::UnknownRule),
+            ]
+        );
+
+        // a rule with the same name that does something else
+        let wrong: Vec<Rewrite<SymbolLang, ()>> =
+            vec![rewrite!("comm"; "(+ ?a ?b)" => "(+ ?a (+ ?b 0))")];
+        let first_comm = (1..flat.len())
+            .find(|&i| flat[i].to_string().contains("comm"))
+            .unwrap();
+        match explanation.verify_proof(&wrong) {
+            Err(ProofError::InvalidRewrite { step, rule, .. }) => {
+                assert_eq!(step, first_comm);
+                assert_eq!(rule, Symbol::from("comm"));
+            }
+            other => panic!("expected an invalid rewrite, got {:?}", other),
+        }
+
+        explanation.check_proof(&rules);
+    }
+
+    #[test]
+    fn union_with_reason() {
+        use crate::*;
+
+        let rules: Vec<Rewrite<SymbolLang, ()>> = vec![rewrite!("typed"; "(f ?a)" => "(g ?a)")];
+        let mut egraph = EGraph::<SymbolLang, ()>::default().with_explanations_enabled();
+        let a = egraph.add_expr(&"(h a)".parse().unwrap());
+        let b = egraph.add_expr(&"(f b)".parse().unwrap());
+        assert!(egraph.union_with_reason(a, b, "typed"));
+        assert!(!egraph.union_with_reason(b, a, "typed"));
+        let runner = Runner::default().with_egraph(egraph).run(&rules);
+        let mut egraph = runner.egraph;
+
+        let start = "(h a)".parse().unwrap();
+        let goal = "(g b)".parse().unwrap();
+        let mut explanation = egraph.explain_equivalence(&start, &goal);
+        assert_eq!(
+            explanation.get_flat_strings(),
+            vec!["(h a)", "(Axiom=> typed (f b))", "(Rewrite=> typed (g b))"]
+        );
+        let flat = explanation.make_flat_explanation();
+        assert_eq!(flat[1].kind, RewriteKind::Axiom);
+        assert_eq!(flat[2].kind, RewriteKind::Rule);
+
+        // the axiom isn't checked against the rule with the same name
+        let report = explanation.verify_proof(&rules).unwrap();
+        assert_eq!(report.steps, 2);
+        assert_eq!(
+            report.unchecked,
+            vec![UncheckedStep {
+                step: 1,
+                rule: Symbol::from("typed"),
+                location: vec![],
+                reason: UncheckedReason::Axiom,
+            }]
+        );
+    }
+
+    fn assert_same_egraph<N: Analysis<SymbolLang>>(
+        a: &EGraph<SymbolLang, N>,
+        b: &EGraph<SymbolLang, N>,
+    ) {
+        assert_eq!(format!("{:?}", a.dump()), format!("{:?}", b.dump()));
+        assert_eq!(a.memo, b.memo);
+        assert_eq!(a.classes_by_op, b.classes_by_op);
+        assert_eq!(a.unionfind.size(), b.unionfind.size());
+        for i in 0..a.unionfind.size() {
+            assert_eq!(a.find(Id::from(i)), b.find(Id::from(i)));
+        }
+    }
+
+    #[test]
+    fn push_pop() {
+        use SymbolLang as S;
+
+        crate::init_logger();
+        let mut egraph = EGraph::<S, ()>::default();
+        let a = egraph.add_expr(&"(f (g a) b)".parse().unwrap());
+        let b = egraph.add_expr(&"(f (g c) b)".parse().unwrap());
+        egraph.rebuild();
+        let original = egraph.clone();
+
+        egraph.push();
+        let x = egraph.add(S::leaf("a"));
+        let y = egraph.add(S::leaf("c"));
+        egraph.union(x, y);
+        egraph.rebuild();
+        assert_eq!(egraph.find(a), egraph.find(b));
+        let after_first = egraph.clone();
+
+        egraph.push();
+        let z = egraph.add_expr(&"(h (f (g a) b))".parse().unwrap());
+        egraph.union(z, a);
+        egraph.rebuild();
+        assert_eq!(egraph.n_checkpoints(), 2);
+
+        egraph.pop();
+        assert_same_egraph(&egraph, &after_first);
+
+        egraph.pop();
+        assert_same_egraph(&egraph, &original);
+        assert_ne!(egraph.find(a), egraph.find(b));
+        assert_eq!(egraph.n_checkpoints(), 0);
+    }
+
+    #[test]
+    fn push_pop_explanations() {
+        crate::init_logger();
+        let mut egraph = EGraph::<SymbolLang, ()>::default().with_explanations_enabled();
+        let start: RecExpr<SymbolLang> = "(+ x (* y 1))".parse().unwrap();
+        let goal: RecExpr<SymbolLang> = "(+ x y)".parse().unwrap();
+        let root = egraph.add_expr(&start);
+        egraph.rebuild();
+        let original = egraph.clone();
+
+        egraph.push();
+        egraph.union_instantiations(
+            &"(* y 1)".parse().unwrap(),
+            &"y".parse().unwrap(),
+            &Default::default(),
+            "mul-one",
+        );
+        egraph.rebuild();
+        assert_eq!(egraph.lookup_expr(&goal), Some(egraph.find(root)));
+        let explanation = egraph.explain_equivalence(&start, &goal);
+        assert_eq!(explanation.explanation_trees.len(), 2);
+
+        egraph.pop();
+        assert_same_egraph(&egraph, &original);
+        assert_eq!(egraph.lookup_expr(&goal), None);
+
+        // the egraph is still usable after backtracking
+        egraph.union_instantiations(
+            &"(* y 1)".parse().unwrap(),
+            &"y".parse().unwrap(),
+            &Default::default(),
+            "mul-one",
+        );
+        egraph.rebuild();
+        egraph.explain_equivalence(&start, &goal);
+    }
+
+    #[test]
+    fn retain_reachable() {
+        use SymbolLang as S;
+
+        crate::init_logger();
+        let mut egraph = EGraph::<S, ()>::default();
+        let junk = egraph.add_expr(&"(h (f x) z)".parse().unwrap());
+        let root = egraph.add_expr(&"(+ (f x) (f y))".parse().unwrap());
+        let x = egraph.add(S::leaf("x"));
+        let w = egraph.add(S::leaf("w"));
+        egraph.union(w, x);
+        egraph.rebuild();
+        assert_eq!(egraph.number_of_classes(), 7);
+
+        let remap = egraph.retain_reachable(&[root]);
+        assert_eq!(egraph.number_of_classes(), 5);
+        assert!(!remap.contains_key(&junk));
+        assert_eq!(remap[&w], remap[&x]);
+        for id in remap.values() {
+            assert!(usize::from(*id) < 5);
+        }
+        assert_eq!(egraph.lookup_expr(&"(h (f x) z)".parse().unwrap()), None);
+        assert_eq!(egraph.lookup(S::leaf("z")), None);
+
+        // parent lists are intact, so congruence still works
+        let root = remap[&root];
+        let x = remap[&x];
+        let y = egraph.lookup(S::leaf("y")).unwrap();
+        egraph.union(x, y);
+        egraph.rebuild();
+        let fx = egraph.lookup_expr(&"(f w)".parse().unwrap()).unwrap();
+        assert_eq!(egraph.lookup_expr(&"(f y)".parse().unwrap()), Some(fx));
+        assert_eq!(
+            egraph.lookup_expr(&"(+ (f x) (f x))".parse().unwrap()),
+            Some(egraph.find(root))
+        );
+        assert_eq!(egraph.number_of_classes(), 3);
+    }
+
+    #[test]
+    fn approx_memory_usage() {
+        use crate::util::children_heap_size;
+        define_language! {
+            enum Arith {
+                Num(i32),
+                "+" = Add([Id; 2]),
+            }
+        }
+
+        let (a, b) = (Id::from(0), Id::from(1));
+        assert_eq!(children_heap_size(&Arith::Add([a, b])), 0);
+        assert_eq!(children_heap_size(&Arith::Num(1)), 0);
+        assert_eq!(children_heap_size(&SymbolLang::new("f", vec![a, b])), 8);
+        assert_eq!(children_heap_size(&SymbolLang::leaf("x")), 0);
+
+        let expr = "(+ (+ 1 2) (+ 3 4))".parse().unwrap();
+        let mut egraph = EGraph::<SymbolLang, ()>::default();
+        egraph.add_expr(&expr);
+        egraph.rebuild();
+        let usage = egraph.approx_memory_usage();
+        assert_eq!(usage.explain, 0);
+        assert_eq!(usage.uncanon_memo, 0);
+        assert_eq!(
+            usage.total(),
+            usage.memo + usage.classes + usage.parents + usage.other
+        );
+
+        let mut egraph = EGraph::<SymbolLang, ()>::default().with_explanations_enabled();
+        egraph.add_expr(&expr);
+        egraph.rebuild();
+        let with_explanations = egraph.approx_memory_usage();
+        assert!(with_explanations.explain > 0);
+        assert!(with_explanations.uncanon_memo > 0);
+        assert!(with_explanations.total() > usage.total());
+    }
+
     #[cfg(all(feature = "serde-1", feature = "serde_json"))]
     #[test]
     fn test_serde() {
@@ -0,0 +1,284 @@
+use std::fmt::{self, Debug};
+
+use crate::util::{HashMap, HashSet};
+use crate::{Analysis, EGraph, Id, Language, RecExpr};
+
+/// The number of terms represented by an e-class,
+/// as counted by a [`TermEnumerator`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TermCount {
+    /// A finite number of terms.
+    /// This saturates at [`u128::MAX`], so that means "at least that many".
+    Finite(u128),
+    /// Infinitely many terms, because the e-class can reach a cycle.
+    Infinite,
+}
+
+impl TermCount {
+    fn add(self, other: Self) -> Self {
+        match (self, other) {
+            (TermCount::Finite(a), TermCount::Finite(b)) => TermCount::Finite(a.saturating_add(b)),
+            _ => TermCount::Infinite,
+        }
+    }
+
+    fn mul(self, other: Self) -> Self {
+        match (self, other) {
+            (TermCount::Finite(0), _) | (_, TermCount::Finite(0)) => TermCount::Finite(0),
+            (TermCount::Finite(a), TermCount::Finite(b)) => TermCount::Finite(a.saturating_mul(b)),
+            _ => TermCount::Infinite,
+        }
+    }
+}
+
+/** Counting and enumerating the terms represented by the e-classes of an
+[`EGraph`].
+
+Counting is useful to see how much an e-graph has grown, and enumerating
+all the terms of an e-class (up to some size) is useful to test that
+rewrite rules only make equivalent terms equal.
+
+An e-class has [infinitely many](TermCount::Infinite) terms if it can reach
+a cycle, e.g. `x` in an e-graph where `x = (+ x 0)`.
+E-nodes with a child e-class that has no terms at all (which can only
+happen through cycles) are ignored.
+
+```
+use egg::*;
+
+let mut egraph = EGraph::<SymbolLang, ()>::default();
+let a = egraph.add_expr(&"(+ x y)".parse().unwrap());
+let b = egraph.add_expr(&"(+ y x)".parse().unwrap());
+let x = egraph.add_expr(&"x".parse().unwrap());
+let z = egraph.add_expr(&"(* x 1)".parse().unwrap());
+egraph.union(a, b);
+egraph.union(x, z);
+egraph.rebuild();
+
+let enumerator = TermEnumerator::new(&egraph);
+assert_eq!(enumerator.count(a), TermCount::Infinite);
+
+let terms: Vec<String> = enumerator.terms(a, 5).map(|t| t.to_string()).collect();
+assert_eq!(terms, vec![
+    "(+ x y)", "(+ y x)",
+    "(+ (* x 1) y)", "(+ y (* x 1))",
+]);
+
+let y = egraph.lookup(SymbolLang::leaf("y")).unwrap();
+assert_eq!(TermEnumerator::new(&egraph).count(y), TermCount::Finite(1));
+```
+**/
+pub struct TermEnumerator<'a, L: Language, N: Analysis<L>> {
+    egraph: &'a EGraph<L, N>,
+    counts: HashMap<Id, TermCount>,
+}
+
+impl<'a, L: Language, N: Analysis<L>> Debug for TermEnumerator<'a, L, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TermEnumerator")
+            .field("egraph", self.egraph)
+            .field("counts", &self.counts)
+            .finish()
+    }
+}
+
+impl<'a, L: Language, N: Analysis<L>> TermEnumerator<'a, L, N> {
+    /// Create a new `TermEnumerator`, counting the terms of every e-class.
+    pub fn new(egraph: &'a EGraph<L, N>) -> Self {
+        let mut enumerator = Self {
+            egraph,
+            counts: HashMap::default(),
+        };
+        enumerator.count_terms();
+        enumerator
+    }
+
+    /// The number of terms represented by the given e-class.
+    pub fn count(&self, eclass: Id) -> TermCount {
+        let eclass = self.egraph.find(eclass);
+        self.counts
+            .get(&eclass)
+            .copied()
+            .unwrap_or(TermCount::Finite(0))
+    }
+
+    /// Lazily enumerate the terms of the given e-class with at most
+    /// `max_size` e-nodes, smallest first.
+    ///
+    /// Pass `usize::MAX` to enumerate all the terms, but note that this
+    /// never ends if the e-class has [infinitely many](TermCount::Infinite).
+    ///
+    /// Only the terms being built are kept in memory, so subterms are
+    /// enumerated again for every term they are part of.
+    pub fn terms(&self, eclass: Id, max_size: usize) -> Terms<'_, 'a, L, N> {
+        Terms {
+            enumerator: self,
+            eclass: self.egraph.find(eclass),
+            max_size,
+            size: 0,
+            remaining: self.count(eclass),
+            current: Box::new(std::iter::empty()),
+        }
+    }
+
+    /// Whether the e-node has a term, i.e., all its children do.
+    fn is_productive(&self, node: &L) -> bool {
+        node.all(|child| self.counts.contains_key(&self.egraph.find(child)))
+    }
+
+    fn count_terms(&mut self) {
+        let egraph = self.egraph;
+
+        // first, find the e-classes that have any terms at all
+        let mut todo: Vec<Id> = egraph.classes().map(|c| c.id).collect();
+        while let Some(id) = todo.pop() {
+            if self.counts.contains_key(&id) {
+                continue;
+            }
+            if egraph[id].iter().any(|node| self.is_productive(node)) {
+                self.counts.insert(id, TermCount::Infinite);
+                todo.extend(egraph[id].parents.iter().map(|(_, p)| egraph.find(*p)));
+            }
+        }
+
+        // then count them bottom-up, starting from the e-classes that only
+        // have leaves; e-classes that are never reached can reach a cycle,
+        // so they have infinitely many terms
+        let mut waiting: HashMap<Id, HashSet<Id>> = HashMap::default();
+        let mut ready: Vec<Id> = vec![];
+        for &id in self.counts.keys() {
+            let children: HashSet<Id> = egraph[id]
+                .iter()
+                .filter(|node| self.is_productive(node))
+                .flat_map(|node| node.children().iter().map(|&c| egraph.find(c)))
+                .collect();
+            if children.is_empty() {
+                ready.push(id);
+            } else {
+                waiting.insert(id, children);
+            }
+        }
+
+        let mut counted: HashSet<Id> = HashSet::default();
+        while let Some(id) = ready.pop() {
+            let mut count = TermCount::Finite(0);
+            for node in egraph[id].iter().filter(|node| self.is_productive(node)) {
+                let children = node.fold(TermCount::Finite(1), |product, child| {
+                    product.mul(self.counts[&egraph.find(child)])
+                });
+                count = count.add(children);
+            }
+            self.counts.insert(id, count);
+            counted.insert(id);
+
+            for (_, parent) in &egraph[id].parents {
+                let parent = egraph.find(*parent);
+                if let Some(children) = waiting.get_mut(&parent) {
+                    children.remove(&id);
+                    if children.is_empty() && !counted.contains(&parent) {
+                        waiting.remove(&parent);
+                        ready.push(parent);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Lazily enumerates the terms of the e-class with exactly `size`
+    /// e-nodes, each as a list of nodes like in a [`RecExpr`].
+    fn terms_of_size(&self, eclass: Id, size: usize) -> Box<dyn Iterator<Item = Vec<L>> + '_> {
+        let nodes = self.egraph[eclass].iter();
+        Box::new(
+            nodes
+                .filter(move |node| self.is_productive(node))
+                .flat_map(move |node| {
+                    let children = self.children_terms(node.children(), size - 1);
+                    children.map(move |(mut term, roots)| {
+                        let mut roots = roots.into_iter();
+                        term.push(node.clone().map_children(|_| roots.next().unwrap()));
+                        term
+                    })
+                }),
+        )
+    }
+
+    /// Lazily enumerates the ways to pick terms for the `children` with
+    /// `size` e-nodes in total, each as the nodes of the picked terms one
+    /// after the other, and the indices of their roots.
+    fn children_terms<'s>(
+        &'s self,
+        children: &'s [Id],
+        size: usize,
+    ) -> Box<dyn Iterator<Item = (Vec<L>, Vec<Id>)> + 's> {
+        let (&first, rest) = match children.split_first() {
+            Some(split) => split,
+            None => return Box::new((size == 0).then(|| (vec![], vec![])).into_iter()),
+        };
+        if size < children.len() {
+            return Box::new(std::iter::empty());
+        }
+        let first = self.egraph.find(first);
+        // the last child gets all the size that is left
+        let min_size = if rest.is_empty() { size } else { 1 };
+        Box::new((min_size..=size - rest.len()).flat_map(move |first_size| {
+            self.terms_of_size(first, first_size).flat_map(move |term| {
+                let rest_terms = self.children_terms(rest, size - first_size);
+                rest_terms.map(move |(rest_nodes, rest_roots)| {
+                    let offset = term.len();
+                    let shift = |id: Id| Id::from(usize::from(id) + offset);
+                    let mut nodes = term.clone();
+                    nodes.extend(rest_nodes.into_iter().map(|n| n.map_children(shift)));
+                    let mut roots = vec![Id::from(offset - 1)];
+                    roots.extend(rest_roots.into_iter().map(shift));
+                    (nodes, roots)
+                })
+            })
+        }))
+    }
+}
+
+/// A lazy iterator over the terms of an e-class, smallest first.
+/// See [`TermEnumerator::terms`].
+pub struct Terms<'e, 'a, L: Language, N: Analysis<L>> {
+    enumerator: &'e TermEnumerator<'a, L, N>,
+    eclass: Id,
+    max_size: usize,
+    size: usize,
+    remaining: TermCount,
+    current: Box<dyn Iterator<Item = Vec<L>> + 'e>,
+}
+
+impl<'e, 'a, L: Language, N: Analysis<L>> Debug for Terms<'e, 'a, L, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Terms")
+            .field("eclass", &self.eclass)
+            .field("max_size", &self.max_size)
+            .field("size", &self.size)
+            .field("remaining", &self.remaining)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'e, 'a, L: Language, N: Analysis<L>> Iterator for Terms<'e, 'a, L, N> {
+    type Item = RecExpr<L>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(term) = self.current.next() {
+                if let TermCount::Finite(n) = &mut self.remaining {
+                    // a saturated count is just a lower bound
+                    if *n != u128::MAX {
+                        *n -= 1;
+                    }
+                }
+                return Some(RecExpr::from(term));
+            }
+
+            if self.remaining == TermCount::Finite(0) || self.size >= self.max_size {
+                return None;
+            }
+            self.size += 1;
+            self.current = self.enumerator.terms_of_size(self.eclass, self.size);
+        }
+    }
+}
@@ -1,17 +1,29 @@
 use crate::Symbol;
 use crate::{
-    util::pretty_print, Analysis, ENodeOrVar, HashMap, HashSet, Id, Language, PatternAst, Rewrite,
-    Var,
+    util::{children_heap_size, hash_table_size, pretty_print},
+    Analysis, ENodeOrVar, HashMap, HashSet, Id, Language, PatternAst, Rewrite, Var,
 };
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::fmt::{self, Debug, Display, Formatter};
 use std::rc::Rc;
 
 use symbolic_expressions::Sexp;
+use thiserror::Error;
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) enum Justification {
     Rule(Symbol),
+    // a rule that matched several terms, each paired with the node of the
+    // e-class bound to it, see [`MultiPattern`](crate::MultiPattern)
+    RuleWithPremises(Symbol, Vec<(Id, Id)>),
+    // a union from an analysis, with the reason it gave,
+    // see [`EGraph::union_by_analysis`](crate::EGraph::union_by_analysis)
+    Analysis(Symbol),
+    // a union given by the user with a reason,
+    // see [`EGraph::union_with_reason`](crate::EGraph::union_with_reason)
+    Axiom(Symbol),
     Congruence,
 }
 
@@ -37,6 +49,20 @@ pub struct Explain<L: Language> {
     explainfind: Vec<ExplainNode<L>>,
     #[cfg_attr(feature = "serde-1", serde(with = "vectorize"))]
     pub uncanon_memo: HashMap<L, Id>,
+    #[cfg_attr(feature = "serde-1", serde(skip))]
+    #[cfg_attr(feature = "serde-1", serde(default = "Vec::new"))]
+    checkpoints: Vec<ExplainCheckpoint<L>>,
+    #[cfg_attr(feature = "serde-1", serde(default))]
+    pub(crate) optimize_explanation_lengths: bool,
+}
+
+/// Undo information for the proof forest, see [`EGraph::push`](crate::EGraph::push).
+#[derive(Debug, Clone)]
+struct ExplainCheckpoint<L: Language> {
+    n_nodes: usize,
+    // original versions of the nodes modified since the checkpoint
+    nodes: HashMap<Id, ExplainNode<L>>,
+    uncanon_memo: HashMap<L, Option<Id>>,
 }
 
 /// Explanation trees are the compact representation showing
@@ -62,6 +88,22 @@ pub type FlatExplanation<L> = Vec<FlatTerm<L>>;
 // given two adjacent nodes and the direction of the proof
 type ExplainCache<L> = HashMap<(Id, Id), Rc<TreeTerm<L>>>;
 type NodeExplanationCache<L> = HashMap<Id, Rc<TreeTerm<L>>>;
+// the congruent nodes of each node, with the length of the proof between them
+type Congruences = Rc<HashMap<Id, Vec<(Id, usize)>>>;
+
+/// The proof forest seen as a graph, with an edge between any two nodes
+/// that are congruent, for finding short explanations.
+/// Only valid while the proof forest doesn't change.
+struct ProofGraph {
+    // the nodes whose `next` is each node
+    pointed_by: Vec<Vec<Id>>,
+    roots: HashMap<Id, Id>,
+    // the length of the flattened proof along the proof forest
+    distances: HashMap<(Id, Id), usize>,
+    // the congruence edges of each tree of the proof forest, by root
+    congruences: HashMap<Id, Congruences>,
+    in_progress: HashSet<(Id, Id)>,
+}
 
 /** A data structure representing an explanation that two terms are equivalent.
 
@@ -120,6 +162,12 @@ impl<L: Language + Display> Explanation<L> {
     /// "Rewrite=>" indicates that the previous term is rewritten to the current term
     /// and "Rewrite<=" indicates that the current term is rewritten to the previous term.
     /// The name of the rule or the reason provided to [`union_instantiations`](super::EGraph::union_instantiations).
+    /// Unions made by an [`Analysis`] use "Analysis=>" and "Analysis<=" instead, with the
+    /// reason provided to [`union_by_analysis`](super::EGraph::union_by_analysis),
+    /// and unions given as axioms use "Axiom=>" and "Axiom<=", with the reason provided to
+    /// [`union_with_reason`](super::EGraph::union_with_reason).
+    /// Rewrites by a rule with a [`MultiPattern`](crate::MultiPattern) searcher have a
+    /// "(Premises ...)" after the expression, with the proof of each premise.
     ///
     /// The following example shows that `(+ 1 (- a (* (- 2 1) a))) = 1`
     /// Example explanation:
@@ -222,7 +270,7 @@ impl<L: Language + Display> Explanation<L> {
         shared: &mut HashSet<*const TreeTerm<L>>,
         to_let_bind: &mut Vec<Rc<TreeTerm<L>>>,
     ) {
-        for proof in &term.child_proofs {
+        for proof in term.child_proofs.iter().chain(&term.premises) {
             for child in proof {
                 self.find_to_let_bind(child.clone(), shared, to_let_bind);
             }
@@ -242,6 +290,12 @@ impl<L: Language + Display> Explanation<L> {
     /// "Rewrite=>" indicates that the previous term is rewritten to the current term
     /// and "Rewrite<=" indicates that the current term is rewritten to the previous term.
     /// The name of the rule or the reason provided to [`union_instantiations`](super::EGraph::union_instantiations).
+    /// Unions made by an [`Analysis`] use "Analysis=>" and "Analysis<=" instead, with the
+    /// reason provided to [`union_by_analysis`](super::EGraph::union_by_analysis),
+    /// and unions given as axioms use "Axiom=>" and "Axiom<=", with the reason provided to
+    /// [`union_with_reason`](super::EGraph::union_with_reason).
+    /// Rewrites by a rule with a [`MultiPattern`](crate::MultiPattern) searcher have a
+    /// "(Premises ...)" after the expression, with the proof of each premise.
     ///
     /// Example explanation:
     /// ```text
@@ -279,9 +333,52 @@ impl<L: Language> Explanation<L> {
         }
     }
 
-    /// Check the validity of the explanation with respect to the given rules.
+    /// The number of rewrites in the tree representation of the explanation,
+    /// counting the ones in shared [`TreeTerm`]s only once.
+    pub fn get_tree_size(&self) -> usize {
+        let mut seen = HashSet::default();
+        TreeTerm::proof_size(&self.explanation_trees, &mut seen)
+    }
+
+    /// The number of rewrites in the flattened explanation,
+    /// i.e. the number of terms in it minus one.
+    pub fn get_flat_size(&mut self) -> usize {
+        self.make_flat_explanation().len() - 1
+    }
+
+    /// Check the validity of the explanation with respect to the given rules,
+    /// panicking if it's invalid.
     /// This only is able to check rule applications when the rules are implement `get_pattern_ast`.
+    /// See [`verify_proof`](Explanation::verify_proof) for a version that doesn't panic.
     pub fn check_proof<'a, R, N: Analysis<L>>(&mut self, rules: R)
+    where
+        R: IntoIterator<Item = &'a Rewrite<L, N>>,
+        L: 'a,
+        N: 'a,
+    {
+        if let Err(error) = self.verify_proof(rules) {
+            panic!("Invalid proof: {}", error);
+        }
+    }
+
+    /// Check the validity of the explanation with respect to the given rules.
+    ///
+    /// Each step of the [flattened explanation](Explanation::make_flat_explanation)
+    /// must rewrite exactly one subterm of the previous term, leaving the rest unchanged.
+    /// Rewrites by a rule with a [`Pattern`](crate::Pattern) or a
+    /// [`MultiPattern`](crate::MultiPattern) on both sides are
+    /// checked by applying the rule; the others can't be checked and are
+    /// listed in the [`ProofReport`], as are the rewrites by a rule that isn't
+    /// in `rules`, like the reasons given to
+    /// [`union_instantiations`](crate::EGraph::union_instantiations),
+    /// and the rewrites that [aren't made by a rule](FlatTerm::kind).
+    /// The proofs of the [premises](FlatTerm::premises) of a rewrite are checked
+    /// the same way, and the rewrites in them that can't be checked are listed
+    /// at the step and location of that rewrite.
+    pub fn verify_proof<'a, R, N: Analysis<L>>(
+        &mut self,
+        rules: R,
+    ) -> Result<ProofReport, ProofError>
     where
         R: IntoIterator<Item = &'a Rewrite<L, N>>,
         L: 'a,
@@ -289,56 +386,223 @@ impl<L: Language> Explanation<L> {
     {
         let rules: Vec<&Rewrite<L, N>> = rules.into_iter().collect();
         let rule_table = Explain::make_rule_table(rules.as_slice());
-        self.make_flat_explanation();
-        let flat_explanation = self.flat_explanation.as_ref().unwrap();
-        assert!(!flat_explanation[0].has_rewrite_forward());
-        assert!(!flat_explanation[0].has_rewrite_backward());
-        for i in 0..flat_explanation.len() - 1 {
-            let current = &flat_explanation[i];
-            let next = &flat_explanation[i + 1];
-
-            let has_forward = next.has_rewrite_forward();
-            let has_backward = next.has_rewrite_backward();
-            assert!(has_forward ^ has_backward);
-
-            if has_forward {
-                assert!(self.check_rewrite_at(current, next, &rule_table, true));
-            } else {
-                assert!(self.check_rewrite_at(current, next, &rule_table, false));
+        let flat_explanation = self.make_flat_explanation();
+        Explanation::verify_flat_proof(flat_explanation, &rule_table)
+    }
+
+    fn verify_flat_proof<N: Analysis<L>>(
+        flat_explanation: &[FlatTerm<L>],
+        rule_table: &HashMap<Symbol, &Rewrite<L, N>>,
+    ) -> Result<ProofReport, ProofError> {
+        if let Some(location) = flat_explanation[0].rewrite_location() {
+            return Err(ProofError::UnexpectedRewrite { step: 0, location });
+        }
+
+        let mut report = ProofReport {
+            steps: flat_explanation.len() - 1,
+            unchecked: vec![],
+        };
+        for step in 1..flat_explanation.len() {
+            let mut found = false;
+            Explanation::verify_rewrite_at(
+                &flat_explanation[step - 1],
+                &flat_explanation[step],
+                rule_table,
+                step,
+                &mut vec![],
+                &mut found,
+                &mut report,
+            )?;
+            if !found {
+                return Err(ProofError::MissingRewrite { step });
             }
         }
+        Ok(report)
     }
 
-    fn check_rewrite_at<N: Analysis<L>>(
-        &self,
+    fn verify_rewrite_at<N: Analysis<L>>(
         current: &FlatTerm<L>,
         next: &FlatTerm<L>,
         table: &HashMap<Symbol, &Rewrite<L, N>>,
-        is_forward: bool,
-    ) -> bool {
-        if is_forward && next.forward_rule.is_some() {
-            let rule_name = next.forward_rule.as_ref().unwrap();
-            if let Some(rule) = table.get(rule_name) {
-                Explanation::check_rewrite(current, next, rule)
-            } else {
-                // give up when the rule is not provided
-                true
+        step: usize,
+        location: &mut Vec<usize>,
+        found: &mut bool,
+        report: &mut ProofReport,
+    ) -> Result<(), ProofError> {
+        let (rule, forward) = match (next.forward_rule, next.backward_rule) {
+            (None, None) => {
+                if !current.node.matches(&next.node) {
+                    return Err(ProofError::TermMismatch {
+                        step,
+                        location: location.clone(),
+                    });
+                }
+                for (i, (left, right)) in current.children.iter().zip(&next.children).enumerate() {
+                    location.push(i);
+                    Explanation::verify_rewrite_at(
+                        left, right, table, step, location, found, report,
+                    )?;
+                    location.pop();
+                }
+                return Ok(());
             }
-        } else if !is_forward && next.backward_rule.is_some() {
-            let rule_name = next.backward_rule.as_ref().unwrap();
-            if let Some(rule) = table.get(rule_name) {
-                Explanation::check_rewrite(next, current, rule)
-            } else {
-                true
+            (Some(rule), None) => (rule, true),
+            (None, Some(rule)) => (rule, false),
+            (Some(_), Some(_)) => {
+                return Err(ProofError::UnexpectedRewrite {
+                    step,
+                    location: location.clone(),
+                })
             }
-        } else {
-            for (left, right) in current.children.iter().zip(next.children.iter()) {
-                if !self.check_rewrite_at(left, right, table, is_forward) {
-                    return false;
+        };
+
+        if *found {
+            return Err(ProofError::UnexpectedRewrite {
+                step,
+                location: location.clone(),
+            });
+        }
+        *found = true;
+        for (i, child) in next.children.iter().enumerate() {
+            if let Some(mut child_location) = child.rewrite_location() {
+                child_location.insert(0, i);
+                let mut extra = location.clone();
+                extra.extend(child_location);
+                return Err(ProofError::UnexpectedRewrite {
+                    step,
+                    location: extra,
+                });
+            }
+        }
+
+        for (premise, proof) in next.premises.iter().enumerate() {
+            match Explanation::verify_flat_proof(proof, table) {
+                Ok(premise_report) => {
+                    for unchecked in premise_report.unchecked {
+                        report.unchecked.push(UncheckedStep {
+                            step,
+                            location: location.clone(),
+                            ..unchecked
+                        });
+                    }
+                }
+                Err(error) => {
+                    return Err(ProofError::InvalidPremise {
+                        step,
+                        location: location.clone(),
+                        premise,
+                        error: Box::new(error),
+                    })
                 }
             }
-            true
         }
+
+        let reason = match table.get(&rule) {
+            _ if next.kind == RewriteKind::Analysis => Some(UncheckedReason::Analysis),
+            _ if next.kind == RewriteKind::Axiom => Some(UncheckedReason::Axiom),
+            None => Some(UncheckedReason::UnknownRule),
+            Some(rewrite) if next.premises.is_empty() => match (
+                rewrite.searcher.get_pattern_ast(),
+                rewrite.applier.get_pattern_ast(),
+            ) {
+                (None, _) => Some(UncheckedReason::CustomSearcher),
+                (_, None) => Some(UncheckedReason::CustomApplier),
+                (Some(_), Some(_)) => None,
+            },
+            Some(rewrite) => match (
+                rewrite.searcher.get_multi_pattern_ast(),
+                rewrite.applier.get_multi_pattern_ast(),
+            ) {
+                (None, _) => Some(UncheckedReason::CustomSearcher),
+                (_, None) => Some(UncheckedReason::CustomApplier),
+                (Some(_), Some(_)) => None,
+            },
+        };
+        if let Some(reason) = reason {
+            report.unchecked.push(UncheckedStep {
+                step,
+                rule,
+                location: location.clone(),
+                reason,
+            });
+        } else {
+            let rewrite = table[&rule];
+            let (from, to) = if forward {
+                (current, next)
+            } else {
+                (next, current)
+            };
+            let valid = if next.premises.is_empty() {
+                Explanation::check_rewrite(from, to, rewrite)
+            } else {
+                Explanation::check_multi_rewrite(from, to, &next.premises, rewrite)
+            };
+            if !valid {
+                return Err(ProofError::InvalidRewrite {
+                    step,
+                    rule,
+                    forward,
+                    location: location.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the rewrite follows from the premises by a rule with
+    /// [`MultiPattern`](crate::MultiPattern)s on both sides: the premises
+    /// must match the searcher, and `next` must be an instantiation of a
+    /// pattern of the applier bound to the same variable as `current`.
+    fn check_multi_rewrite<N: Analysis<L>>(
+        current: &FlatTerm<L>,
+        next: &FlatTerm<L>,
+        premises: &[FlatExplanation<L>],
+        rewrite: &Rewrite<L, N>,
+    ) -> bool {
+        let searcher = rewrite.searcher.get_multi_pattern_ast().unwrap();
+        let applier = rewrite.applier.get_multi_pattern_ast().unwrap();
+        if premises.len() != searcher.len() {
+            return false;
+        }
+
+        // each premise goes from the term bound to the variable to the
+        // term matched by the pattern
+        let mut bindings: HashMap<Var, &FlatTerm<L>> = Default::default();
+        for ((var, pattern), premise) in searcher.iter().zip(premises) {
+            let bound = &premise[0];
+            if bindings
+                .get(var)
+                .map_or(false, |existing| *existing != bound)
+            {
+                return false;
+            }
+            bindings.insert(*var, bound);
+            let nodes = pattern.as_ref();
+            if !premise
+                .last()
+                .unwrap()
+                .make_bindings(nodes, nodes.len() - 1, &mut bindings)
+            {
+                return false;
+            }
+        }
+
+        // like the applier, bind each variable to the last pattern instantiated for it
+        let mut instantiations: HashMap<Var, FlatTerm<L>> = Default::default();
+        for (var, pattern) in applier {
+            let nodes = pattern.as_ref();
+            let instantiated = {
+                let mut all_bindings = bindings.clone();
+                all_bindings.extend(instantiations.iter().map(|(v, term)| (*v, term)));
+                let instantiated = FlatTerm::from_pattern(nodes, nodes.len() - 1, &all_bindings);
+                if all_bindings.get(var) == Some(&current) && &instantiated == next {
+                    return true;
+                }
+                instantiated
+            };
+            instantiations.insert(*var, instantiated);
+        }
+        false
     }
 
     // if the rewrite is just patterns, then it can check it
@@ -349,16 +613,123 @@ impl<L: Language> Explanation<L> {
     ) -> bool {
         if let Some(lhs) = rewrite.searcher.get_pattern_ast() {
             if let Some(rhs) = rewrite.applier.get_pattern_ast() {
-                let rewritten = current.rewrite(lhs, rhs);
-                if &rewritten != next {
-                    return false;
-                }
+                return current.try_rewrite(lhs, rhs).as_ref() == Some(next);
             }
         }
         true
     }
 }
 
+/// A summary of an [`Explanation`] checked by
+/// [`verify_proof`](Explanation::verify_proof).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ProofReport {
+    /// The number of rewrites in the flattened explanation.
+    pub steps: usize,
+    /// The rewrites that couldn't be checked.
+    pub unchecked: Vec<UncheckedStep>,
+}
+
+impl ProofReport {
+    /// Whether every rewrite of the explanation was checked.
+    pub fn is_fully_checked(&self) -> bool {
+        self.unchecked.is_empty()
+    }
+}
+
+/// A rewrite of a flattened explanation that
+/// [`verify_proof`](Explanation::verify_proof) couldn't check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct UncheckedStep {
+    /// The index in the flattened explanation of the term produced by the rewrite.
+    pub step: usize,
+    /// The name of the rule.
+    pub rule: Symbol,
+    /// The path of child indices from the root of the term to the rewritten subterm.
+    pub location: Vec<usize>,
+    /// Why the rewrite couldn't be checked.
+    pub reason: UncheckedReason,
+}
+
+/// Why a rewrite couldn't be checked, see [`UncheckedStep`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UncheckedReason {
+    /// The rule wasn't given to [`verify_proof`](Explanation::verify_proof),
+    /// e.g. because it's a reason given to
+    /// [`union_instantiations`](crate::EGraph::union_instantiations).
+    UnknownRule,
+    /// The rule's [`Searcher`](crate::Searcher) isn't a [`Pattern`](crate::Pattern).
+    CustomSearcher,
+    /// The rule's [`Applier`](crate::Applier) isn't a [`Pattern`](crate::Pattern).
+    CustomApplier,
+    /// The rewrite was made by an [`Analysis`], see
+    /// [`union_by_analysis`](crate::EGraph::union_by_analysis).
+    Analysis,
+    /// The rewrite is an axiom, see
+    /// [`union_with_reason`](crate::EGraph::union_with_reason).
+    Axiom,
+}
+
+/// Why an [`Explanation`] is invalid, see [`verify_proof`](Explanation::verify_proof).
+///
+/// Steps are indices in the flattened explanation, so step `i` is the
+/// rewrite from term `i - 1` to term `i`.
+/// Locations are paths of child indices from the root of the term.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[non_exhaustive]
+pub enum ProofError {
+    /// The first term is annotated with a rewrite,
+    /// or a term is annotated with more than one.
+    #[error("unexpected rewrite in step {step} at {location:?}")]
+    UnexpectedRewrite {
+        /// The index of the term in the flattened explanation.
+        step: usize,
+        /// Where the unexpected rewrite is.
+        location: Vec<usize>,
+    },
+    /// A term after the first one isn't annotated with a rewrite.
+    #[error("step {step} has no rewrite")]
+    MissingRewrite {
+        /// The index of the term in the flattened explanation.
+        step: usize,
+    },
+    /// Applying the rule doesn't give the rewritten subterm.
+    #[error("step {step} is not a valid {} application of {rule} at {location:?}", if *.forward { "forward" } else { "backward" })]
+    InvalidRewrite {
+        /// The index of the term in the flattened explanation.
+        step: usize,
+        /// The name of the rule.
+        rule: Symbol,
+        /// Whether the rule was applied forward.
+        forward: bool,
+        /// Where the rule was applied.
+        location: Vec<usize>,
+    },
+    /// The proof of a premise of a rewrite is invalid.
+    #[error("premise {premise} of step {step} at {location:?} is invalid: {error}")]
+    InvalidPremise {
+        /// The index of the term in the flattened explanation.
+        step: usize,
+        /// Where the rewrite is.
+        location: Vec<usize>,
+        /// The index of the premise.
+        premise: usize,
+        /// Why the proof of the premise is invalid.
+        error: Box<ProofError>,
+    },
+    /// The term changed somewhere other than in the rewritten subterm.
+    #[error("step {step} changes the term at {location:?} without a rewrite")]
+    TermMismatch {
+        /// The index of the term in the flattened explanation.
+        step: usize,
+        /// Where the terms differ.
+        location: Vec<usize>,
+    },
+}
+
 /// An explanation for a term and its equivalent children.
 /// Each child is a proof transforming the initial child into the final child term.
 /// The initial term is given by taking each first sub-term
@@ -383,6 +754,28 @@ pub struct TreeTerm<L: Language> {
     pub forward_rule: Option<Symbol>,
     /// A list of child proofs, each transforming the initial term to the final term for that child.
     pub child_proofs: Vec<TreeExplanation<L>>,
+    /// For a rule with a [`MultiPattern`](crate::MultiPattern) searcher, a proof for each
+    /// of its patterns, from the term of the e-class bound to the pattern's variable to
+    /// the term the pattern matched.
+    /// Empty for other rewrites.
+    pub premises: Vec<TreeExplanation<L>>,
+    /// What made the rewrite, if there is one.
+    pub kind: RewriteKind,
+}
+
+/// What justifies a rewrite in an explanation, see [`TreeTerm::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RewriteKind {
+    /// A rewrite rule, or a reason given to
+    /// [`union_instantiations`](crate::EGraph::union_instantiations).
+    Rule,
+    /// An [`Analysis`]; the rule is the reason given to
+    /// [`union_by_analysis`](crate::EGraph::union_by_analysis).
+    Analysis,
+    /// An axiom given by the user; the rule is the reason given to
+    /// [`union_with_reason`](crate::EGraph::union_with_reason).
+    Axiom,
 }
 
 impl<L: Language> TreeTerm<L> {
@@ -393,9 +786,27 @@ impl<L: Language> TreeTerm<L> {
             backward_rule: None,
             forward_rule: None,
             child_proofs,
+            premises: vec![],
+            kind: RewriteKind::Rule,
         }
     }
 
+    fn proof_size(proof: &[Rc<TreeTerm<L>>], seen: &mut HashSet<*const TreeTerm<L>>) -> usize {
+        let mut size = 0;
+        for tree in proof {
+            if !seen.insert(Rc::as_ptr(tree)) {
+                continue;
+            }
+            if tree.forward_rule.is_some() || tree.backward_rule.is_some() {
+                size += 1;
+            }
+            for child_proof in tree.child_proofs.iter().chain(&tree.premises) {
+                size += TreeTerm::proof_size(child_proof, seen);
+            }
+        }
+        size
+    }
+
     fn flatten_proof(proof: &[Rc<TreeTerm<L>>]) -> FlatExplanation<L> {
         let mut flat_proof: FlatExplanation<L> = vec![];
         for tree in proof {
@@ -452,6 +863,12 @@ impl<L: Language> TreeTerm<L> {
 
         proof[0].backward_rule = self.backward_rule;
         proof[0].forward_rule = self.forward_rule;
+        proof[0].kind = self.kind;
+        proof[0].premises = self
+            .premises
+            .iter()
+            .map(|premise| TreeTerm::flatten_proof(premise))
+            .collect();
 
         proof
     }
@@ -468,7 +885,10 @@ impl<L: Language> TreeTerm<L> {
 /// When [`backward_rule`](FlatTerm::backward_rule) is provided, the previous FlatTerm is given by applying
 /// the rule to this FlatTerm.
 /// Rules are either the string of the name of the rule or the reason provided to
-/// [`union_instantiations`](super::EGraph::union_instantiations).
+/// [`union_instantiations`](super::EGraph::union_instantiations),
+/// [`union_by_analysis`](super::EGraph::union_by_analysis)
+/// or [`union_with_reason`](super::EGraph::union_with_reason),
+/// see [`kind`](FlatTerm::kind).
 ///
 #[derive(Debug, Clone, Eq)]
 pub struct FlatTerm<L: Language> {
@@ -481,6 +901,10 @@ pub struct FlatTerm<L: Language> {
     pub forward_rule: Option<Symbol>,
     /// The children of this FlatTerm.
     pub children: FlatExplanation<L>,
+    /// The proofs of the premises of the rule, see [`TreeTerm::premises`].
+    pub premises: Vec<FlatExplanation<L>>,
+    /// What made the rewrite, if there is one.
+    pub kind: RewriteKind,
 }
 
 impl<L: Language + Display> Display for FlatTerm<L> {
@@ -527,6 +951,13 @@ impl<L: Language> FlatTerm<L> {
             self.backward_rule = other.backward_rule;
         }
 
+        if !other.premises.is_empty() {
+            self.premises = other.premises.clone();
+        }
+        if other.forward_rule.is_some() || other.backward_rule.is_some() {
+            self.kind = other.kind;
+        }
+
         for (left, right) in self.children.iter_mut().zip(other.children.iter()) {
             left.combine_rewrites(right);
         }
@@ -544,7 +975,7 @@ impl<L: Language + Display> FlatTerm<L> {
     /// See [`get_flat_sexps`](Explanation::get_flat_sexps) for the format of these expressions.
     pub fn get_sexp(&self) -> Sexp {
         let op = Sexp::String(self.node.to_string());
-        let mut expr = if self.node.is_leaf() {
+        let expr = if self.node.is_leaf() {
             op
         } else {
             let mut vec = vec![op];
@@ -554,23 +985,22 @@ impl<L: Language + Display> FlatTerm<L> {
             Sexp::List(vec)
         };
 
-        if let Some(rule_name) = &self.backward_rule {
-            expr = Sexp::List(vec![
-                Sexp::String("Rewrite<=".to_string()),
-                Sexp::String((*rule_name).to_string()),
-                expr,
-            ]);
-        }
-
-        if let Some(rule_name) = &self.forward_rule {
-            expr = Sexp::List(vec![
-                Sexp::String("Rewrite=>".to_string()),
-                Sexp::String((*rule_name).to_string()),
-                expr,
-            ]);
-        }
-
-        expr
+        let premises = self.premises.iter().map(|premise| {
+            if premise.len() == 1 {
+                premise[0].get_sexp()
+            } else {
+                let mut items = vec![Sexp::String("Explanation".to_string())];
+                items.extend(premise.iter().map(|term| term.get_sexp()));
+                Sexp::List(items)
+            }
+        });
+        add_rewrite_to_sexp(
+            expr,
+            self.forward_rule,
+            self.backward_rule,
+            self.kind,
+            premises,
+        )
     }
 }
 
@@ -595,53 +1025,97 @@ impl<L: Language + Display> TreeTerm<L> {
         bindings: &HashMap<*const TreeTerm<L>, Sexp>,
     ) -> Sexp {
         let op = Sexp::String(self.node.to_string());
-        let mut expr = if self.node.is_leaf() {
+        let expr = if self.node.is_leaf() {
             op
         } else {
             let mut vec = vec![op];
             for child in &self.child_proofs {
-                assert!(!child.is_empty());
-                if child.len() == 1 {
-                    if let Some(existing) = bindings.get(&(&*child[0] as *const TreeTerm<L>)) {
-                        vec.push(existing.clone());
-                    } else {
-                        vec.push(child[0].get_sexp_with_bindings(bindings));
-                    }
-                } else {
-                    let mut child_expressions = vec![Sexp::String("Explanation".to_string())];
-                    for child_explanation in child.iter() {
-                        if let Some(existing) =
-                            bindings.get(&(&**child_explanation as *const TreeTerm<L>))
-                        {
-                            child_expressions.push(existing.clone());
-                        } else {
-                            child_expressions
-                                .push(child_explanation.get_sexp_with_bindings(bindings));
-                        }
-                    }
-                    vec.push(Sexp::List(child_expressions));
-                }
+                vec.push(TreeTerm::proof_sexp(child, bindings));
             }
             Sexp::List(vec)
         };
 
-        if let Some(rule_name) = &self.backward_rule {
-            expr = Sexp::List(vec![
-                Sexp::String("Rewrite<=".to_string()),
-                Sexp::String((*rule_name).to_string()),
-                expr,
-            ]);
-        }
+        let premises = self
+            .premises
+            .iter()
+            .map(|premise| TreeTerm::proof_sexp(premise, bindings));
+        add_rewrite_to_sexp(
+            expr,
+            self.forward_rule,
+            self.backward_rule,
+            self.kind,
+            premises,
+        )
+    }
 
-        if let Some(rule_name) = &self.forward_rule {
-            expr = Sexp::List(vec![
-                Sexp::String("Rewrite=>".to_string()),
-                Sexp::String((*rule_name).to_string()),
-                expr,
-            ]);
+    /// A proof as the s-expression of its only term, or wrapped with "(Explanation ...)".
+    fn proof_sexp(proof: &[Rc<TreeTerm<L>>], bindings: &HashMap<*const TreeTerm<L>, Sexp>) -> Sexp {
+        assert!(!proof.is_empty());
+        let term_sexp = |term: &Rc<TreeTerm<L>>| {
+            if let Some(existing) = bindings.get(&(&**term as *const TreeTerm<L>)) {
+                existing.clone()
+            } else {
+                term.get_sexp_with_bindings(bindings)
+            }
+        };
+        if proof.len() == 1 {
+            term_sexp(&proof[0])
+        } else {
+            let mut expressions = vec![Sexp::String("Explanation".to_string())];
+            expressions.extend(proof.iter().map(term_sexp));
+            Sexp::List(expressions)
         }
+    }
+}
 
-        expr
+/// Wrap the s-expression of a term with "(Rewrite=> rule-name expression)"
+/// or "(Rewrite<= rule-name expression)", followed by "(Premises ...)" if the
+/// rule has premises.
+/// Rewrites made by an analysis or given as axioms use "Analysis" or "Axiom"
+/// instead of "Rewrite".
+fn add_rewrite_to_sexp(
+    mut expr: Sexp,
+    forward_rule: Option<Symbol>,
+    backward_rule: Option<Symbol>,
+    kind: RewriteKind,
+    premises: impl Iterator<Item = Sexp>,
+) -> Sexp {
+    let kind = match kind {
+        RewriteKind::Rule => "Rewrite",
+        RewriteKind::Analysis => "Analysis",
+        RewriteKind::Axiom => "Axiom",
+    };
+    let mut premises = premises.peekable();
+    let premises = if premises.peek().is_some() {
+        let mut items = vec![Sexp::String("Premises".to_string())];
+        items.extend(premises);
+        Some(Sexp::List(items))
+    } else {
+        None
+    };
+
+    if let Some(rule_name) = backward_rule {
+        expr = Sexp::List(vec![
+            Sexp::String(format!("{}<=", kind)),
+            Sexp::String(rule_name.to_string()),
+            expr,
+        ]);
+    }
+
+    if let Some(rule_name) = forward_rule {
+        expr = Sexp::List(vec![
+            Sexp::String(format!("{}=>", kind)),
+            Sexp::String(rule_name.to_string()),
+            expr,
+        ]);
+    }
+
+    match (expr, premises) {
+        (Sexp::List(mut items), Some(premises)) => {
+            items.push(premises);
+            Sexp::List(items)
+        }
+        (expr, _) => expr,
     }
 }
 
@@ -653,17 +1127,47 @@ impl<L: Language> FlatTerm<L> {
             backward_rule: None,
             forward_rule: None,
             children,
+            premises: vec![],
+            kind: RewriteKind::Rule,
         }
     }
 
     /// Rewrite the FlatTerm by matching the lhs and substituting the rhs.
     /// The lhs must be guaranteed to match.
     pub fn rewrite(&self, lhs: &PatternAst<L>, rhs: &PatternAst<L>) -> FlatTerm<L> {
+        self.try_rewrite(lhs, rhs).unwrap_or_else(|| {
+            panic!(
+                "Invalid proof: {:?} does not match the pattern {:?}",
+                self, lhs
+            )
+        })
+    }
+
+    /// Like [`rewrite`](FlatTerm::rewrite), but returns `None` if the lhs doesn't match.
+    pub fn try_rewrite(&self, lhs: &PatternAst<L>, rhs: &PatternAst<L>) -> Option<FlatTerm<L>> {
         let lhs_nodes = lhs.as_ref();
         let rhs_nodes = rhs.as_ref();
         let mut bindings = Default::default();
-        self.make_bindings(lhs_nodes, lhs_nodes.len() - 1, &mut bindings);
-        FlatTerm::from_pattern(rhs_nodes, rhs_nodes.len() - 1, &bindings)
+        if !self.make_bindings(lhs_nodes, lhs_nodes.len() - 1, &mut bindings) {
+            return None;
+        }
+        Some(FlatTerm::from_pattern(
+            rhs_nodes,
+            rhs_nodes.len() - 1,
+            &bindings,
+        ))
+    }
+
+    /// The path of child indices to the first subterm with a rewrite, if any.
+    fn rewrite_location(&self) -> Option<Vec<usize>> {
+        if self.forward_rule.is_some() || self.backward_rule.is_some() {
+            return Some(vec![]);
+        }
+        self.children.iter().enumerate().find_map(|(i, child)| {
+            let mut location = child.rewrite_location()?;
+            location.insert(0, i);
+            Some(location)
+        })
     }
 
     /// Checks if this term or any child has a [`forward_rule`](FlatTerm::forward_rule).
@@ -710,33 +1214,51 @@ impl<L: Language> FlatTerm<L> {
         pattern: &[ENodeOrVar<L>],
         location: usize,
         bindings: &mut HashMap<Var, &'a FlatTerm<L>>,
-    ) {
+    ) -> bool {
         match &pattern[location] {
             ENodeOrVar::Var(var) => {
                 if let Some(existing) = bindings.get(var) {
-                    if existing != &self {
-                        panic!(
-                            "Invalid proof: binding for variable {:?} does not match between {:?} \n and \n {:?}",
-                            var, existing, self);
-                    }
+                    // the binding for the variable must be the same everywhere
+                    existing == &self
                 } else {
                     bindings.insert(*var, self);
+                    true
                 }
             }
             ENodeOrVar::ENode(node) => {
                 // The node must match the rewrite or the proof is invalid.
-                assert!(node.matches(&self.node));
-                let mut counter = 0;
-                node.for_each(|child| {
-                    self.children[counter].make_bindings(pattern, usize::from(child), bindings);
-                    counter += 1;
-                });
+                node.matches(&self.node)
+                    && node
+                        .children()
+                        .iter()
+                        .zip(&self.children)
+                        .all(|(child, term)| {
+                            term.make_bindings(pattern, usize::from(*child), bindings)
+                        })
             }
         }
     }
 }
 
 impl<L: Language> Explain<L> {
+    /// Approximate bytes used by the proof forest and the uncanonical memo,
+    /// see [`EGraph::approx_memory_usage`](crate::EGraph::approx_memory_usage).
+    pub(crate) fn approx_memory_usage(&self) -> (usize, usize) {
+        let forest = self.explainfind.capacity() * std::mem::size_of::<ExplainNode<L>>()
+            + self
+                .explainfind
+                .iter()
+                .map(|n| children_heap_size(&n.node))
+                .sum::<usize>();
+        let uncanon_memo = hash_table_size::<(L, Id)>(self.uncanon_memo.capacity())
+            + self
+                .uncanon_memo
+                .keys()
+                .map(children_heap_size)
+                .sum::<usize>();
+        (forest, uncanon_memo)
+    }
+
     fn node_to_explanation(
         &self,
         node_id: Id,
@@ -821,16 +1343,58 @@ impl<L: Language> Explain<L> {
         Explain {
             explainfind: vec![],
             uncanon_memo: Default::default(),
+            checkpoints: vec![],
+            optimize_explanation_lengths: false,
+        }
+    }
+
+    pub(crate) fn push(&mut self) {
+        self.checkpoints.push(ExplainCheckpoint {
+            n_nodes: self.explainfind.len(),
+            nodes: Default::default(),
+            uncanon_memo: Default::default(),
+        });
+    }
+
+    pub(crate) fn pop(&mut self) {
+        let checkpoint = self.checkpoints.pop().expect("No checkpoint to pop");
+        self.explainfind.truncate(checkpoint.n_nodes);
+        for (id, node) in checkpoint.nodes {
+            self.explainfind[usize::from(id)] = node;
+        }
+        for (node, id) in checkpoint.uncanon_memo {
+            match id {
+                Some(id) => self.uncanon_memo.insert(node, id),
+                None => self.uncanon_memo.remove(&node),
+            };
+        }
+    }
+
+    /// Get a node of the proof forest for modification,
+    /// saving the original if there is an active checkpoint.
+    fn node_mut(&mut self, id: Id) -> &mut ExplainNode<L> {
+        let explainfind = &mut self.explainfind;
+        if let Some(checkpoint) = self.checkpoints.last_mut() {
+            if usize::from(id) < checkpoint.n_nodes {
+                checkpoint
+                    .nodes
+                    .entry(id)
+                    .or_insert_with(|| explainfind[usize::from(id)].clone());
+            }
         }
+        &mut explainfind[usize::from(id)]
     }
 
     pub(crate) fn set_existance_reason(&mut self, node: Id, existance_node: Id) {
-        self.explainfind[usize::from(node)].existance_node = existance_node;
+        self.node_mut(node).existance_node = existance_node;
     }
 
     pub(crate) fn add(&mut self, node: L, set: Id, existance_node: Id) -> Id {
         assert_eq!(self.explainfind.len(), usize::from(set));
-        self.uncanon_memo.insert(node.clone(), set);
+        let old = self.uncanon_memo.insert(node.clone(), set);
+        if let Some(checkpoint) = self.checkpoints.last_mut() {
+            checkpoint.uncanon_memo.entry(node.clone()).or_insert(old);
+        }
         self.explainfind.push(ExplainNode {
             node,
             justification: Justification::Congruence,
@@ -847,11 +1411,12 @@ impl<L: Language> Explain<L> {
         let next = self.explainfind[usize::from(node)].next;
         if next != node {
             self.make_leader(next);
-            self.explainfind[usize::from(next)].justification =
-                self.explainfind[usize::from(node)].justification.clone();
-            self.explainfind[usize::from(next)].is_rewrite_forward =
-                !self.explainfind[usize::from(node)].is_rewrite_forward;
-            self.explainfind[usize::from(next)].next = node;
+            let justification = self.explainfind[usize::from(node)].justification.clone();
+            let is_rewrite_forward = !self.explainfind[usize::from(node)].is_rewrite_forward;
+            let next_node = self.node_mut(next);
+            next_node.justification = justification;
+            next_node.is_rewrite_forward = is_rewrite_forward;
+            next_node.next = node;
         }
     }
 
@@ -872,15 +1437,27 @@ impl<L: Language> Explain<L> {
         }
 
         self.make_leader(node1);
-        self.explainfind[usize::from(node1)].next = node2;
-        self.explainfind[usize::from(node1)].justification = justification;
-        self.explainfind[usize::from(node1)].is_rewrite_forward = true;
+        let node = self.node_mut(node1);
+        node.next = node2;
+        node.justification = justification;
+        node.is_rewrite_forward = true;
     }
 
     pub(crate) fn explain_equivalence(&mut self, left: Id, right: Id) -> Explanation<L> {
         let mut cache = Default::default();
         let mut enode_cache = Default::default();
-        Explanation::new(self.explain_enodes(left, right, &mut cache, &mut enode_cache))
+        if self.optimize_explanation_lengths {
+            let mut graph = self.make_proof_graph();
+            Explanation::new(self.explain_shortest(
+                left,
+                right,
+                &mut graph,
+                &mut cache,
+                &mut enode_cache,
+            ))
+        } else {
+            Explanation::new(self.explain_enodes(left, right, &mut cache, &mut enode_cache))
+        }
     }
 
     pub(crate) fn explain_existance(&mut self, left: Id) -> Explanation<L> {
@@ -1047,7 +1624,7 @@ impl<L: Language> Explain<L> {
         }
 
         let term = match justification {
-            Justification::Rule(name) => {
+            Justification::Rule(name) | Justification::RuleWithPremises(name, _) => {
                 let mut rewritten =
                     (*self.node_to_explanation(next, node_explanation_cache)).clone();
                 if rule_direction {
@@ -1055,9 +1632,31 @@ impl<L: Language> Explain<L> {
                 } else {
                     rewritten.backward_rule = Some(*name);
                 }
+                if let Justification::RuleWithPremises(_, premises) = justification {
+                    rewritten.premises = premises
+                        .iter()
+                        .map(|(bound, matched)| {
+                            self.explain_enodes(*bound, *matched, cache, node_explanation_cache)
+                        })
+                        .collect();
+                }
 
                 Rc::new(rewritten)
             }
+            Justification::Analysis(reason) | Justification::Axiom(reason) => {
+                let mut rewritten =
+                    (*self.node_to_explanation(next, node_explanation_cache)).clone();
+                if rule_direction {
+                    rewritten.forward_rule = Some(*reason);
+                } else {
+                    rewritten.backward_rule = Some(*reason);
+                }
+                rewritten.kind = match justification {
+                    Justification::Analysis(_) => RewriteKind::Analysis,
+                    _ => RewriteKind::Axiom,
+                };
+                Rc::new(rewritten)
+            }
             Justification::Congruence => {
                 // add the children proofs to the last explanation
                 let current_node = &self.explainfind[usize::from(current)].node;
@@ -1085,4 +1684,259 @@ impl<L: Language> Explain<L> {
 
         term
     }
+
+    fn make_proof_graph(&self) -> ProofGraph {
+        let mut pointed_by = vec![vec![]; self.explainfind.len()];
+        for (i, node) in self.explainfind.iter().enumerate() {
+            if node.next != Id::from(i) {
+                pointed_by[usize::from(node.next)].push(Id::from(i));
+            }
+        }
+        ProofGraph {
+            pointed_by,
+            roots: Default::default(),
+            distances: Default::default(),
+            congruences: Default::default(),
+            in_progress: Default::default(),
+        }
+    }
+
+    fn root(&self, mut node: Id, graph: &mut ProofGraph) -> Id {
+        let start = node;
+        if let Some(&root) = graph.roots.get(&start) {
+            return root;
+        }
+        loop {
+            let next = self.explainfind[usize::from(node)].next;
+            if next == node {
+                graph.roots.insert(start, node);
+                return node;
+            }
+            node = next;
+        }
+    }
+
+    /// The length of the flattened proof that `left` and `right` are equal
+    /// given by [`explain_enodes`](Explain::explain_enodes).
+    fn forest_distance(&self, left: Id, right: Id, graph: &mut ProofGraph) -> usize {
+        if left == right {
+            return 0;
+        }
+        let key = (left.min(right), left.max(right));
+        if let Some(&distance) = graph.distances.get(&key) {
+            return distance;
+        }
+
+        let ancestor = self.common_ancestor(left, right);
+        let mut distance: usize = 0;
+        for node in self
+            .get_nodes(left, ancestor)
+            .into_iter()
+            .chain(self.get_nodes(right, ancestor))
+        {
+            let cost = match node.justification {
+                Justification::Rule(_)
+                | Justification::RuleWithPremises(..)
+                | Justification::Analysis(_)
+                | Justification::Axiom(_) => 1,
+                Justification::Congruence => {
+                    self.congruence_distance(node.current, node.next, graph)
+                }
+            };
+            distance = distance.saturating_add(cost);
+        }
+        graph.distances.insert(key, distance);
+        distance
+    }
+
+    /// The length of the flattened proof that two congruent nodes are equal,
+    /// proving their children equal along the proof forest.
+    fn congruence_distance(&self, left: Id, right: Id, graph: &mut ProofGraph) -> usize {
+        let left_node = &self.explainfind[usize::from(left)].node;
+        let right_node = &self.explainfind[usize::from(right)].node;
+        let mut distance: usize = 0;
+        for (left_child, right_child) in left_node.children().iter().zip(right_node.children()) {
+            let cost = self.forest_distance(*left_child, *right_child, graph);
+            distance = distance.saturating_add(cost);
+        }
+        distance
+    }
+
+    /// The congruent nodes of each node in the tree of the proof forest
+    /// with the given root, with the [`congruence_distance`](Explain::congruence_distance)
+    /// between them.
+    fn congruences(&self, root: Id, graph: &mut ProofGraph) -> Congruences {
+        if let Some(congruences) = graph.congruences.get(&root) {
+            return congruences.clone();
+        }
+
+        let mut members = vec![root];
+        let mut i = 0;
+        while i < members.len() {
+            members.extend(graph.pointed_by[usize::from(members[i])].iter().copied());
+            i += 1;
+        }
+
+        // congruent nodes are the same once their children are canonicalized
+        let mut buckets: HashMap<L, Vec<Id>> = Default::default();
+        for &member in &members {
+            let canonical = self.explainfind[usize::from(member)]
+                .node
+                .clone()
+                .map_children(|child| self.root(child, graph));
+            buckets.entry(canonical).or_default().push(member);
+        }
+
+        let mut congruences: HashMap<Id, Vec<(Id, usize)>> = Default::default();
+        for bucket in buckets.values() {
+            for (i, &left) in bucket.iter().enumerate() {
+                for &right in &bucket[i + 1..] {
+                    let distance = self.congruence_distance(left, right, graph);
+                    congruences.entry(left).or_default().push((right, distance));
+                    congruences.entry(right).or_default().push((left, distance));
+                }
+            }
+        }
+
+        let congruences = Rc::new(congruences);
+        graph.congruences.insert(root, congruences.clone());
+        congruences
+    }
+
+    /// Find the path from `left` to `right` that gives the shortest flattened
+    /// proof, assuming the children of congruent nodes are proven equal along
+    /// the proof forest.
+    /// Each step is the nodes it goes between and, if it's a rewrite,
+    /// the node of the proof forest whose edge justifies it.
+    fn shortest_path(
+        &self,
+        left: Id,
+        right: Id,
+        graph: &mut ProofGraph,
+    ) -> Vec<(Id, Id, Option<Id>)> {
+        let root = self.root(left, graph);
+        let congruences = self.congruences(root, graph);
+
+        let mut distances: HashMap<Id, usize> = Default::default();
+        let mut previous: HashMap<Id, (Id, Option<Id>)> = Default::default();
+        let mut todo = BinaryHeap::new();
+        distances.insert(left, 0);
+        todo.push(Reverse((0, left)));
+
+        while let Some(Reverse((distance, node))) = todo.pop() {
+            if node == right {
+                break;
+            }
+            if distances[&node] < distance {
+                continue;
+            }
+
+            let mut edges = vec![];
+            let next = self.explainfind[usize::from(node)].next;
+            let forest_edges: Vec<(Id, Id)> = std::iter::once((next, node))
+                .filter(|&(next, _)| next != node)
+                .chain(graph.pointed_by[usize::from(node)].iter().map(|&n| (n, n)))
+                .collect();
+            for (neighbor, edge) in forest_edges {
+                let explain_node = &self.explainfind[usize::from(edge)];
+                match explain_node.justification {
+                    Justification::Rule(_)
+                    | Justification::RuleWithPremises(..)
+                    | Justification::Analysis(_)
+                    | Justification::Axiom(_) => edges.push((neighbor, 1, Some(edge))),
+                    Justification::Congruence => {
+                        let cost = self.congruence_distance(node, neighbor, graph);
+                        edges.push((neighbor, cost, None));
+                    }
+                }
+            }
+            if let Some(congruent) = congruences.get(&node) {
+                edges.extend(congruent.iter().map(|&(n, cost)| (n, cost, None)));
+            }
+
+            for (neighbor, cost, edge) in edges {
+                let new_distance = distance.saturating_add(cost);
+                if distances.get(&neighbor).map_or(true, |&d| new_distance < d) {
+                    distances.insert(neighbor, new_distance);
+                    previous.insert(neighbor, (node, edge));
+                    todo.push(Reverse((new_distance, neighbor)));
+                }
+            }
+        }
+
+        let mut path = vec![];
+        let mut node = right;
+        while node != left {
+            let (prev, edge) = previous[&node];
+            path.push((prev, node, edge));
+            node = prev;
+        }
+        path.reverse();
+        path
+    }
+
+    /// Like [`explain_enodes`](Explain::explain_enodes), but following the
+    /// [`shortest_path`](Explain::shortest_path) instead of the proof forest.
+    fn explain_shortest(
+        &self,
+        left: Id,
+        right: Id,
+        graph: &mut ProofGraph,
+        cache: &mut ExplainCache<L>,
+        node_explanation_cache: &mut NodeExplanationCache<L>,
+    ) -> TreeExplanation<L> {
+        // fall back to the proof forest instead of going around in circles
+        if !graph.in_progress.insert((left, right)) {
+            return self.explain_enodes(left, right, cache, node_explanation_cache);
+        }
+
+        let mut proof = vec![self.node_to_explanation(left, node_explanation_cache)];
+        for (current, next, edge) in self.shortest_path(left, right, graph) {
+            let term = match edge {
+                Some(edge) => {
+                    let node = &self.explainfind[usize::from(edge)];
+                    let direction = if edge == current {
+                        node.is_rewrite_forward
+                    } else {
+                        !node.is_rewrite_forward
+                    };
+                    self.explain_adjacent(
+                        current,
+                        next,
+                        direction,
+                        &node.justification,
+                        cache,
+                        node_explanation_cache,
+                    )
+                }
+                None => {
+                    if let Some(answer) = cache.get(&(current, next)) {
+                        answer.clone()
+                    } else {
+                        let current_node = &self.explainfind[usize::from(current)].node;
+                        let next_node = &self.explainfind[usize::from(next)].node;
+                        let mut subproofs = vec![];
+                        for (left_child, right_child) in
+                            current_node.children().iter().zip(next_node.children())
+                        {
+                            subproofs.push(self.explain_shortest(
+                                *left_child,
+                                *right_child,
+                                graph,
+                                cache,
+                                node_explanation_cache,
+                            ));
+                        }
+                        let term = Rc::new(TreeTerm::new(current_node.clone(), subproofs));
+                        cache.insert((current, next), term.clone());
+                        term
+                    }
+                }
+            };
+            proof.push(term);
+        }
+
+        graph.in_progress.remove(&(left, right));
+        proof
+    }
 }
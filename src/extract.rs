@@ -1,7 +1,8 @@
 use std::cmp::Ordering;
-use std::fmt::Debug;
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Display};
 
-use crate::util::HashMap;
+use crate::util::{HashMap, HashSet};
 use crate::{Analysis, EClass, EGraph, Id, Language, RecExpr};
 
 /** Extracting a single [`RecExpr`] from an [`EGraph`].
@@ -36,6 +37,31 @@ assert_eq!(best_cost, 1);
 assert_eq!(best, "10".parse().unwrap());
 ```
 
+An `Extractor` borrows the e-graph, so it can't be kept around while the
+e-graph changes. Instead, you can [`detach`](Extractor::detach) it, keep
+modifying the e-graph, and [`attach`](DetachedExtractor::attach) it again.
+That only recomputes the costs of the e-classes that changed in between
+(as tracked by [`EGraph::new_generation`]), rather than starting from scratch.
+Start a new generation before creating the extractor, so the changes made
+before it aren't revisited:
+
+```
+# use egg::*;
+let mut egraph = EGraph::<SymbolLang, ()>::default();
+let root = egraph.add_expr(&"(f (g (h x)))".parse().unwrap());
+egraph.new_generation();
+let extractor = Extractor::new(&egraph, AstSize);
+assert_eq!(extractor.find_best_cost(root), 4);
+let detached = extractor.detach();
+
+let x = egraph.add(SymbolLang::leaf("x"));
+let inner = egraph.add_expr(&"(g (h x))".parse().unwrap());
+egraph.union(x, inner);
+egraph.rebuild();
+
+let extractor = detached.attach(&egraph);
+assert_eq!(extractor.find_best(root).1.to_string(), "(f x)");
+```
 **/
 #[derive(Debug)]
 pub struct Extractor<'a, CF: CostFunction<L>, L: Language, N: Analysis<L>> {
@@ -44,6 +70,157 @@ pub struct Extractor<'a, CF: CostFunction<L>, L: Language, N: Analysis<L>> {
     egraph: &'a EGraph<L, N>,
 }
 
+/// An [`Extractor`] that has been detached from its [`EGraph`] with
+/// [`Extractor::detach`].
+///
+/// It keeps the costs that were found, so that attaching it to the e-graph
+/// again after some [`add`](EGraph::add)s, [`union`](EGraph::union)s, and
+/// [`rebuild`](EGraph::rebuild)s only has to update them.
+#[derive(Debug)]
+pub struct DetachedExtractor<CF: CostFunction<L>, L: Language> {
+    cost_function: CF,
+    costs: HashMap<Id, (CF::Cost, L)>,
+    generation: usize,
+}
+
+impl<CF: CostFunction<L>, L: Language> DetachedExtractor<CF, L> {
+    /// Attach this extractor to the e-graph it was detached from,
+    /// updating its costs to account for any changes made in the meantime.
+    ///
+    /// Only the e-classes that the e-graph
+    /// [reports as changed](EGraph::classes_changed_since) since the
+    /// generation the extractor was detached in (and their ancestors) are
+    /// revisited.
+    /// That includes the changes made in that generation before the
+    /// extractor was created, so call [`EGraph::new_generation`] right
+    /// before creating it to skip them.
+    /// If the e-graph wasn't tracking changes yet (or has
+    /// [forgotten](EGraph::forget_generations_before) that generation),
+    /// every e-class is revisited.
+    ///
+    /// The e-graph must only have grown since the extractor was detached;
+    /// if e-classes were removed (e.g. by
+    /// [`retain_reachable`](EGraph::retain_reachable)), create a new
+    /// [`Extractor`] instead.
+    pub fn attach<N: Analysis<L>>(self, egraph: &EGraph<L, N>) -> Extractor<'_, CF, L, N> {
+        let mut extractor = Extractor {
+            cost_function: self.cost_function,
+            costs: HashMap::default(),
+            egraph,
+        };
+
+        // re-key the old costs by their (possibly merged) canonical e-class
+        for (id, (cost, node)) in self.costs {
+            let root = egraph.find(id);
+            match extractor.costs.get(&root) {
+                Some((old, _)) if *old <= cost => (),
+                _ => {
+                    extractor.costs.insert(root, (cost, node));
+                }
+            }
+        }
+
+        let changed = egraph.classes_changed_since(self.generation, 0);
+        extractor.find_costs(changed);
+        extractor
+    }
+}
+
+/** A term extracted by [`Extractor::find_best_annotated`], along with why
+each of its subterms was chosen.
+
+`nodes` is indexed like the nodes of `expr`: for each subterm it holds the
+e-class it was extracted from, its cost, and the other e-nodes of that
+e-class that were rejected.
+The [`Display`] implementation renders this as an indented cost breakdown.
+
+```
+use egg::*;
+
+let mut egraph = EGraph::<SymbolLang, ()>::default();
+let a = egraph.add_expr(&"(* x 2)".parse().unwrap());
+let b = egraph.add_expr(&"(+ x x)".parse().unwrap());
+let c = egraph.add_expr(&"(<< x 1)".parse().unwrap());
+egraph.union(a, b);
+egraph.union(a, c);
+egraph.rebuild();
+
+let mut extractor = Extractor::new(&egraph, AstSize);
+let tree = extractor.find_best_annotated(a);
+assert_eq!(tree.expr.to_string(), "(+ x x)");
+
+let root = tree.nodes.last().unwrap();
+assert_eq!(root.eclass, egraph.find(a));
+assert_eq!(root.cost, 3);
+assert_eq!(root.rejected.len(), 2);
+assert!(root.rejected.iter().all(|(_, cost)| *cost == Some(3)));
+println!("{}", tree);
+```
+**/
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct CostTree<C, L> {
+    /// The extracted term, the same as [`Extractor::find_best`] would return.
+    pub expr: RecExpr<L>,
+    /// The annotations of each node of `expr`, indexed the same way.
+    pub nodes: Vec<CostTreeNode<C, L>>,
+}
+
+/// The annotations of a subterm in a [`CostTree`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct CostTreeNode<C, L> {
+    /// The e-class this subterm was extracted from.
+    pub eclass: Id,
+    /// The cost of this subterm.
+    pub cost: C,
+    /// The other e-nodes of the e-class, cheapest first, with their cost
+    /// given the best terms of their children, or `None` if they have no
+    /// term (e.g. because of a cycle).
+    /// The children of these e-nodes are e-class ids.
+    pub rejected: Vec<(L, Option<C>)>,
+}
+
+impl<C: Debug, L: Language + Display> CostTree<C, L> {
+    fn fmt_rec(&self, f: &mut fmt::Formatter<'_>, i: usize, depth: usize) -> fmt::Result {
+        let indent = depth * 2;
+        let node = &self.nodes[i];
+        writeln!(
+            f,
+            "{:indent$}{} [e-class {}, cost {:?}]",
+            "",
+            self.expr[Id::from(i)],
+            node.eclass,
+            node.cost,
+            indent = indent
+        )?;
+        for (rejected, cost) in &node.rejected {
+            write!(f, "{:indent$}  rejected ({}", "", rejected, indent = indent)?;
+            for child in rejected.children() {
+                write!(f, " e-class {}", child)?;
+            }
+            match cost {
+                Some(cost) => writeln!(f, "), cost {:?}", cost)?,
+                None => writeln!(f, "), no cost")?,
+            }
+        }
+        for child in self.expr[Id::from(i)].children() {
+            self.fmt_rec(f, usize::from(*child), depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl<C: Debug, L: Language + Display> Display for CostTree<C, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.nodes.is_empty() {
+            Ok(())
+        } else {
+            self.fmt_rec(f, self.nodes.len() - 1, 0)
+        }
+    }
+}
+
 /** A cost function that can be used by an [`Extractor`].
 
 To extract an expression from an [`EGraph`], the [`Extractor`]
@@ -189,6 +366,42 @@ fn cmp<T: PartialOrd>(a: &Option<T>, b: &Option<T>) -> Ordering {
     }
 }
 
+/// Builds a [`RecExpr`] with a term for each of the `roots`, using the e-node
+/// that `choose` picks for each e-class, and returns the indices of the roots
+/// in it. Subterms shared between the roots are only added once.
+pub(crate) fn build_multi_root_expr<'a, L, N>(
+    egraph: &EGraph<L, N>,
+    roots: &[Id],
+    choose: impl Fn(Id) -> &'a L,
+) -> (RecExpr<L>, Vec<Id>)
+where
+    L: Language + 'a,
+    N: Analysis<L>,
+{
+    let mut todo: Vec<Id> = roots.iter().map(|&id| egraph.find(id)).collect();
+    let mut expr = RecExpr::default();
+    // converts e-class ids to e-node ids
+    let mut ids: HashMap<Id, Id> = HashMap::default();
+
+    while let Some(&id) = todo.last() {
+        if ids.contains_key(&id) {
+            todo.pop();
+            continue;
+        }
+        let node = choose(id);
+        if node.all(|child| ids.contains_key(&egraph.find(child))) {
+            let new_id = expr.add(node.clone().map_children(|i| ids[&egraph.find(i)]));
+            ids.insert(id, new_id);
+            todo.pop();
+        } else {
+            todo.extend(node.children().iter().map(|&child| egraph.find(child)));
+        }
+    }
+
+    let root_idxs = roots.iter().map(|&id| ids[&egraph.find(id)]).collect();
+    (expr, root_idxs)
+}
+
 impl<'a, CF, L, N> Extractor<'a, CF, L, N>
 where
     CF: CostFunction<L>,
@@ -208,11 +421,21 @@ where
             egraph,
             cost_function,
         };
-        extractor.find_costs();
+        extractor.find_costs(egraph.classes().map(|c| c.id).collect());
 
         extractor
     }
 
+    /// Detach this extractor from its e-graph, so the e-graph can be
+    /// modified. See [`DetachedExtractor::attach`].
+    pub fn detach(self) -> DetachedExtractor<CF, L> {
+        DetachedExtractor {
+            cost_function: self.cost_function,
+            costs: self.costs,
+            generation: self.egraph.generation(),
+        }
+    }
+
     /// Find the cheapest (lowest cost) represented `RecExpr` in the
     /// given eclass.
     pub fn find_best(&self, eclass: Id) -> (CF::Cost, RecExpr<L>) {
@@ -221,6 +444,77 @@ where
         (cost, expr)
     }
 
+    /// Find the cheapest represented `RecExpr`s of several e-classes at once.
+    ///
+    /// All the terms are put in one [`RecExpr`], so a subterm shared by
+    /// several of them only appears once. The returned ids are the
+    /// indices of the roots in that `RecExpr`, in the same order as the
+    /// given e-classes.
+    ///
+    /// ```
+    /// # use egg::*;
+    /// let mut egraph = EGraph::<SymbolLang, ()>::default();
+    /// let a = egraph.add_expr(&"(f (g x))".parse().unwrap());
+    /// let b = egraph.add_expr(&"(h (g x) (g x))".parse().unwrap());
+    /// egraph.rebuild();
+    ///
+    /// let extractor = Extractor::new(&egraph, AstSize);
+    /// let (expr, roots) = extractor.find_best_multiple(&[a, b]);
+    /// // x and (g x) are shared
+    /// assert_eq!(expr.as_ref().len(), 4);
+    /// assert_eq!(expr[roots[0]], SymbolLang::new("f", vec![Id::from(1)]));
+    /// assert_eq!(expr[roots[1]], SymbolLang::new("h", vec![Id::from(1), Id::from(1)]));
+    /// ```
+    pub fn find_best_multiple(&self, eclasses: &[Id]) -> (RecExpr<L>, Vec<Id>) {
+        build_multi_root_expr(self.egraph, eclasses, |id| self.find_best_node(id))
+    }
+
+    /// Like [`find_best`](Extractor::find_best), but annotates each
+    /// subterm with its cost and the e-nodes that were rejected for it.
+    /// See [`CostTree`].
+    pub fn find_best_annotated(&mut self, eclass: Id) -> CostTree<CF::Cost, L> {
+        let mut tree = CostTree {
+            expr: RecExpr::default(),
+            nodes: vec![],
+        };
+        self.build_annotated(self.egraph.find(eclass), &mut tree, &mut HashMap::default());
+        tree
+    }
+
+    fn build_annotated(
+        &mut self,
+        eclass: Id,
+        tree: &mut CostTree<CF::Cost, L>,
+        built: &mut HashMap<Id, Id>,
+    ) -> Id {
+        if let Some(&id) = built.get(&eclass) {
+            return id;
+        }
+        let egraph = self.egraph;
+        let (cost, best) = self.costs[&eclass].clone();
+        let best = best.map_children(|child| egraph.find(child));
+
+        let mut rejected: Vec<(L, Option<CF::Cost>)> = vec![];
+        for node in egraph[eclass].iter() {
+            let node = node.clone().map_children(|child| egraph.find(child));
+            if node != best {
+                let cost = self.node_total_cost(&node);
+                rejected.push((node, cost));
+            }
+        }
+        rejected.sort_by(|a, b| cmp(&a.1, &b.1));
+
+        let node = best.map_children(|child| self.build_annotated(child, tree, built));
+        let id = tree.expr.add(node);
+        tree.nodes.push(CostTreeNode {
+            eclass,
+            cost,
+            rejected,
+        });
+        built.insert(eclass, id);
+        id
+    }
+
     /// Find the cheapest e-node in the given e-class.
     pub fn find_best_node(&self, eclass: Id) -> &L {
         &self.costs[&self.egraph.find(eclass)].1
@@ -244,23 +538,39 @@ where
         }
     }
 
-    fn find_costs(&mut self) {
-        let mut did_something = true;
-        while did_something {
-            did_something = false;
-
-            for class in self.egraph.classes() {
-                let pass = self.make_pass(class);
-                match (self.costs.get(&class.id), pass) {
-                    (None, Some(new)) => {
-                        self.costs.insert(class.id, new);
-                        did_something = true;
-                    }
-                    (Some(old), Some(new)) if new.0 < old.0 => {
-                        self.costs.insert(class.id, new);
-                        did_something = true;
+    /// Computes costs with a worklist, starting from the given e-classes.
+    /// Whenever the cost of an e-class changes, its parents are revisited.
+    fn find_costs(&mut self, changed: Vec<Id>) {
+        let egraph = self.egraph;
+        let mut todo: VecDeque<Id> = VecDeque::new();
+        let mut queued: HashSet<Id> = HashSet::default();
+        for id in changed {
+            // the parents of a changed e-class may have gotten cheaper even
+            // if the e-class itself doesn't, e.g. after a merge
+            let parents = egraph[id].parents.iter().map(|(_, p)| egraph.find(*p));
+            for id in std::iter::once(id).chain(parents) {
+                if queued.insert(id) {
+                    todo.push_back(id);
+                }
+            }
+        }
+
+        while let Some(id) = todo.pop_front() {
+            queued.remove(&id);
+            let class = &egraph[id];
+            let pass = self.make_pass(class);
+            let improved = match (self.costs.get(&id), pass) {
+                (None, Some(new)) => Some(new),
+                (Some(old), Some(new)) if new.0 < old.0 => Some(new),
+                _ => None,
+            };
+            if let Some(new) = improved {
+                self.costs.insert(id, new);
+                for (_, parent) in &class.parents {
+                    let parent = egraph.find(*parent);
+                    if queued.insert(parent) {
+                        todo.push_back(parent);
                     }
-                    _ => (),
                 }
             }
         }
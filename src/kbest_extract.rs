@@ -0,0 +1,275 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt::Debug;
+
+use crate::util::{HashMap, HashSet};
+use crate::{Analysis, CostFunction, EGraph, Id, Language, RecExpr};
+
+/** Extracting the `k` cheapest [`RecExpr`]s from each e-class of an [`EGraph`].
+
+Where an [`Extractor`](crate::Extractor) only remembers the cheapest enode
+of each e-class, a `KBestExtractor` keeps up to `k` candidates per e-class.
+A candidate is an enode together with a choice of candidate for each of its
+children, so every candidate stands for a different term.
+
+The candidates are found in order of increasing cost, like Dijkstra's
+algorithm, so the [`CostFunction`] must be _monotonic_: an enode must not
+cost less than any of its children, and it must not get cheaper when one of
+its children gets more expensive.
+Each child is chosen on its own, even if several children are in the same
+e-class, so the `k` cheapest terms are found.
+For that, the cost function gets enodes whose children are numbered by
+position, so it should only look at the children through the `costs`
+function it is given.
+
+```
+use egg::*;
+
+let rules: &[Rewrite<SymbolLang, ()>] = &[
+    rewrite!("commute-add"; "(+ ?a ?b)" => "(+ ?b ?a)"),
+    rewrite!("add-0"; "(+ ?a 0)" => "?a"),
+];
+
+let start = "(+ 0 x)".parse().unwrap();
+let runner = Runner::default().with_expr(&start).run(rules);
+let (egraph, root) = (runner.egraph, runner.roots[0]);
+
+let extractor = KBestExtractor::new(&egraph, AstSize, 3);
+let best: Vec<_> = extractor
+    .find_best_k(root)
+    .into_iter()
+    .map(|(cost, expr)| (cost, expr.to_string()))
+    .collect();
+assert_eq!(best[0], (1, "x".to_string()));
+assert_eq!(best[1].0, 3);
+assert_eq!(best[2].0, 3);
+assert_ne!(best[1].1, best[2].1);
+```
+**/
+#[derive(Debug)]
+pub struct KBestExtractor<'a, CF: CostFunction<L>, L: Language, N: Analysis<L>> {
+    k: usize,
+    cost_function: CF,
+    candidates: HashMap<Id, Vec<Candidate<CF::Cost, L>>>,
+    egraph: &'a EGraph<L, N>,
+}
+
+/// An enode and, for each of its children, the index of the candidate of the
+/// child's e-class to use.
+#[derive(Debug, Clone)]
+struct Candidate<C, L> {
+    cost: C,
+    node: L,
+    choices: Vec<usize>,
+}
+
+/// A combination waiting in the priority queue, cheapest first.
+struct QueueEntry<C> {
+    cost: C,
+    // breaks ties in insertion order, to make extraction deterministic
+    order: usize,
+    combination: Combination,
+}
+
+/// An enode of an e-class and a choice for each of its children.
+type Combination = (Id, usize, Vec<usize>);
+
+impl<C: PartialOrd> PartialEq for QueueEntry<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<C: PartialOrd> Eq for QueueEntry<C> {}
+
+impl<C: PartialOrd> PartialOrd for QueueEntry<C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<C: PartialOrd> Ord for QueueEntry<C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed, since BinaryHeap is a max-heap
+        let cost = other
+            .cost
+            .partial_cmp(&self.cost)
+            .expect("KBestExtractor: the CostFunction returned incomparable costs");
+        cost.then_with(|| other.order.cmp(&self.order))
+    }
+}
+
+impl<'a, CF, L, N> KBestExtractor<'a, CF, L, N>
+where
+    CF: CostFunction<L>,
+    L: Language,
+    N: Analysis<L>,
+{
+    /// Create a new `KBestExtractor` given an `EGraph`, a
+    /// `CostFunction`, and how many terms to find for each e-class.
+    ///
+    /// Like [`Extractor::new`](crate::Extractor::new), this does all the
+    /// work on creation.
+    ///
+    /// Panics if the cost function returns costs that cannot be compared,
+    /// like `NaN`.
+    pub fn new(egraph: &'a EGraph<L, N>, cost_function: CF, k: usize) -> Self {
+        let mut extractor = KBestExtractor {
+            k,
+            cost_function,
+            candidates: HashMap::default(),
+            egraph,
+        };
+        extractor.find_candidates();
+        extractor
+    }
+
+    /// Find the (up to) `k` cheapest represented `RecExpr`s in the given
+    /// e-class, cheapest first.
+    pub fn find_best_k(&self, eclass: Id) -> Vec<(CF::Cost, RecExpr<L>)> {
+        (0..self.n_candidates(eclass))
+            .map(|i| self.find_kth_best(eclass, i).unwrap())
+            .collect()
+    }
+
+    /// Find the `i`th cheapest (starting from 0) represented `RecExpr`
+    /// in the given e-class, if there is one among the `k` cheapest.
+    pub fn find_kth_best(&self, eclass: Id, i: usize) -> Option<(CF::Cost, RecExpr<L>)> {
+        let eclass = self.egraph.find(eclass);
+        let cost = self.candidates.get(&eclass)?.get(i)?.cost.clone();
+        let mut expr = RecExpr::default();
+        self.build_expr(eclass, i, &mut expr, &mut HashMap::default());
+        Some((cost, expr))
+    }
+
+    /// The number of terms found for the given e-class,
+    /// which is at most `k`.
+    pub fn n_candidates(&self, eclass: Id) -> usize {
+        let eclass = self.egraph.find(eclass);
+        self.candidates.get(&eclass).map_or(0, |c| c.len())
+    }
+
+    fn build_expr(
+        &self,
+        eclass: Id,
+        i: usize,
+        expr: &mut RecExpr<L>,
+        built: &mut HashMap<(Id, usize), Id>,
+    ) -> Id {
+        if let Some(&id) = built.get(&(eclass, i)) {
+            return id;
+        }
+        let candidate = &self.candidates[&eclass][i];
+        let mut choices = candidate.choices.iter();
+        let node = candidate.node.clone().map_children(|child| {
+            let choice = *choices.next().unwrap();
+            self.build_expr(self.egraph.find(child), choice, expr, built)
+        });
+        let id = expr.add(node);
+        built.insert((eclass, i), id);
+        id
+    }
+
+    fn find_candidates(&mut self) {
+        if self.k == 0 {
+            return;
+        }
+
+        let egraph = self.egraph;
+        let mut queue = BinaryHeap::new();
+        let mut seen: HashSet<Combination> = HashSet::default();
+        // combinations waiting for a child e-class to get another candidate
+        let mut waiting: HashMap<(Id, usize), Vec<Combination>> = HashMap::default();
+        let mut order = 0;
+
+        for class in egraph.classes() {
+            for (i, node) in class.iter().enumerate() {
+                let choices = vec![0; node.len()];
+                let combination = (class.id, i, choices);
+                seen.insert(combination.clone());
+                self.schedule(combination, &mut queue, &mut waiting, &mut order);
+            }
+        }
+
+        while let Some(entry) = queue.pop() {
+            let (eclass, i, choices) = entry.combination;
+            let candidates = self.candidates.entry(eclass).or_default();
+            if candidates.len() >= self.k {
+                continue;
+            }
+            candidates.push(Candidate {
+                cost: entry.cost,
+                node: egraph[eclass].nodes[i].clone(),
+                choices: choices.clone(),
+            });
+            let found = (eclass, candidates.len() - 1);
+
+            for combination in waiting.remove(&found).unwrap_or_default() {
+                self.schedule(combination, &mut queue, &mut waiting, &mut order);
+            }
+
+            for child in 0..choices.len() {
+                let mut next = choices.clone();
+                next[child] += 1;
+                let combination = (eclass, i, next);
+                if seen.insert(combination.clone()) {
+                    self.schedule(combination, &mut queue, &mut waiting, &mut order);
+                }
+            }
+        }
+
+        for class in egraph.classes() {
+            if !self.candidates.contains_key(&class.id) {
+                log::warn!(
+                    "Failed to compute cost for eclass {}: {:?}",
+                    class.id,
+                    class.nodes
+                )
+            }
+        }
+    }
+
+    /// Puts the combination in the queue if all the candidates it chooses
+    /// for its children are known, or waits for the first one that isn't.
+    fn schedule(
+        &mut self,
+        combination: Combination,
+        queue: &mut BinaryHeap<QueueEntry<CF::Cost>>,
+        waiting: &mut HashMap<(Id, usize), Vec<Combination>>,
+        order: &mut usize,
+    ) {
+        let egraph = self.egraph;
+        let (eclass, i, choices) = &combination;
+        let node = &egraph[*eclass].nodes[*i];
+        for (&child, &choice) in node.children().iter().zip(choices) {
+            let child = egraph.find(child);
+            let n_found = self.candidates.get(&child).map_or(0, |c| c.len());
+            if choice >= n_found {
+                waiting
+                    .entry((child, choice))
+                    .or_default()
+                    .push(combination);
+                return;
+            }
+        }
+
+        // number the children by position, since children in the same
+        // e-class may use different candidates
+        let mut positions = 0..;
+        let numbered = node
+            .clone()
+            .map_children(|_| Id::from(positions.next().unwrap()));
+        let candidates = &self.candidates;
+        let cost = self.cost_function.cost(&numbered, |pos| {
+            let pos = usize::from(pos);
+            let child = egraph.find(node.children()[pos]);
+            candidates[&child][choices[pos]].cost.clone()
+        });
+        *order += 1;
+        queue.push(QueueEntry {
+            cost,
+            order: *order,
+            combination,
+        });
+    }
+}
@@ -727,6 +727,11 @@ pub trait Analysis<L: Language>: Sized {
     ///
     /// This function is called immediately following
     /// `Analysis::merge` when unions are performed.
+    /// Use [`union_by_analysis`](EGraph::union_by_analysis) to union e-classes
+    /// here, so that explanations can show why.
+    /// Note that `id` is an e-class, not the e-node the analysis data came
+    /// from, so give `union_by_analysis` the exact e-nodes being justified,
+    /// e.g. from [`add_instantiation_noncanonical`](EGraph::add_instantiation_noncanonical).
     #[allow(unused_variables)]
     fn modify(egraph: &mut EGraph<L, Self>, id: Id) {}
 }
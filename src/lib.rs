@@ -34,19 +34,25 @@ mod macros;
 
 pub mod tutorials;
 
+mod constrained_extract;
+mod dag_extract;
 mod dot;
 mod eclass;
 mod egraph;
+mod enumerate;
 mod explain;
 mod extract;
+mod kbest_extract;
 mod language;
 #[cfg(feature = "lp")]
 mod lp_extract;
 mod machine;
 mod multipattern;
+mod pareto_extract;
 mod pattern;
 mod rewrite;
 mod run;
+mod sample;
 mod subst;
 mod unionfind;
 mod util;
@@ -85,16 +91,25 @@ impl std::fmt::Display for Id {
 pub(crate) use {explain::Explain, explain::Justification, unionfind::UnionFind};
 
 pub use {
+    constrained_extract::{ConstrainedExtractor, ExtractionError},
+    dag_extract::{DagExtractor, LpCostFunction},
     dot::Dot,
     eclass::EClass,
-    egraph::EGraph,
-    explain::{Explanation, FlatExplanation, FlatTerm, TreeExplanation, TreeTerm},
+    egraph::{EGraph, MemoryUsage},
+    enumerate::{TermCount, TermEnumerator, Terms},
+    explain::{
+        Explanation, FlatExplanation, FlatTerm, ProofError, ProofReport, RewriteKind,
+        TreeExplanation, TreeTerm, UncheckedReason, UncheckedStep,
+    },
     extract::*,
+    kbest_extract::KBestExtractor,
     language::*,
     multipattern::*,
+    pareto_extract::{ParetoCost, ParetoExtractor},
     pattern::{ENodeOrVar, Pattern, PatternAst, SearchMatches},
     rewrite::{Applier, Condition, ConditionEqual, ConditionalApplier, Rewrite, Searcher},
     run::*,
+    sample::TermSampler,
     subst::{Subst, Var},
     util::*,
 };
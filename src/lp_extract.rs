@@ -2,24 +2,8 @@ use coin_cbc::{Col, Model, Sense};
 
 use crate::*;
 
-/// A cost function to be used by an [`LpExtractor`].
-#[cfg_attr(docsrs, doc(cfg(feature = "lp")))]
-pub trait LpCostFunction<L: Language, N: Analysis<L>> {
-    /// Returns the cost of the given e-node.
-    ///
-    /// This function may look at other parts of the e-graph to compute the cost
-    /// of the given e-node.
-    fn node_cost(&mut self, egraph: &EGraph<L, N>, eclass: Id, enode: &L) -> f64;
-}
-
-#[cfg_attr(docsrs, doc(cfg(feature = "lp")))]
-impl<L: Language, N: Analysis<L>> LpCostFunction<L, N> for AstSize {
-    fn node_cost(&mut self, _egraph: &EGraph<L, N>, _eclass: Id, _enode: &L) -> f64 {
-        1.0
-    }
-}
-
 /// A structure to perform extraction using integer linear programming.
+/// See [`DagExtractor`] for a pure-Rust alternative that doesn't need a solver.
 /// This uses the [`cbc`](https://projects.coin-or.org/Cbc) solver.
 /// You must have it installed on your machine to use this feature.
 /// You can install it using:
@@ -56,6 +40,7 @@ pub struct LpExtractor<'a, L: Language, N: Analysis<L>> {
     egraph: &'a EGraph<L, N>,
     model: Model,
     vars: HashMap<Id, ClassVars>,
+    node_costs: HashMap<Id, Vec<f64>>,
 }
 
 struct ClassVars {
@@ -64,6 +49,32 @@ struct ClassVars {
     nodes: Vec<Col>,
 }
 
+/// The result of [`LpExtractor::solve_multiple_with_report`]: the extracted
+/// term along with how well the solver did.
+#[cfg_attr(docsrs, doc(cfg(feature = "lp")))]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct LpExtraction<L> {
+    /// The extracted term, containing all the roots.
+    pub expr: RecExpr<L>,
+    /// The indices of the roots in `expr`, in the order they were given.
+    pub roots: Vec<Id>,
+    /// The status reported by CBC.
+    pub status: coin_cbc::raw::Status,
+    /// The secondary status reported by CBC, e.g. whether it stopped
+    /// because of the [timeout](LpExtractor::timeout).
+    pub secondary_status: coin_cbc::raw::SecondaryStatus,
+    /// Whether the solution is proven to be optimal.
+    pub proven_optimal: bool,
+    /// The objective value of the solution, i.e., the DAG cost of `expr`.
+    pub objective: f64,
+    /// The best lower bound on the objective that CBC found.
+    pub best_bound: f64,
+    /// The relative optimality gap, `(objective - best_bound) / |objective|`,
+    /// or 0 if the objective is 0.
+    pub gap: f64,
+}
+
 impl<'a, L, N> LpExtractor<'a, L, N>
 where
     L: Language,
@@ -75,7 +86,8 @@ where
     where
         CF: LpCostFunction<L, N>,
     {
-        let max_order = egraph.total_number_of_nodes() as f64 * 10.0;
+        // a topological order never needs more values than there are classes
+        let max_order = egraph.number_of_classes() as f64;
 
         let mut model = Model::default();
 
@@ -92,11 +104,6 @@ where
             })
             .collect();
 
-        let mut cycles: HashSet<(Id, usize)> = Default::default();
-        find_cycles(egraph, |id, i| {
-            cycles.insert((id, i));
-        });
-
         for (&id, class) in &vars {
             // class active == some node active
             // sum(for node_active in class) == class_active
@@ -107,39 +114,61 @@ where
                 model.set_weight(row, node_active, 1.0);
             }
 
-            for (i, (node, &node_active)) in egraph[id].iter().zip(&class.nodes).enumerate() {
-                if cycles.contains(&(id, i)) {
-                    model.set_col_upper(node_active, 0.0);
-                    model.set_col_lower(node_active, 0.0);
-                    continue;
-                }
-
+            for (node, &node_active) in egraph[id].iter().zip(&class.nodes) {
                 for child in node.children() {
-                    let child_active = vars[child].active;
+                    let child = egraph.find(*child);
+                    if child == id {
+                        // a node that is its own child can never be picked
+                        model.set_col_upper(node_active, 0.0);
+                        continue;
+                    }
+                    let child_vars = &vars[&child];
+
                     // node active implies child active, encoded as:
                     //   node_active <= child_active
                     //   node_active - child_active <= 0
                     let row = model.add_row();
                     model.set_row_upper(row, 0.0);
                     model.set_weight(row, node_active, 1.0);
-                    model.set_weight(row, child_active, -1.0);
+                    model.set_weight(row, child_vars.active, -1.0);
+
+                    // node active implies the class comes after the child in
+                    // a topological order, which rules out cycles:
+                    //   node_active => order >= child_order + 1
+                    //   order - child_order - (max_order + 1) * node_active >= -max_order
+                    let row = model.add_row();
+                    model.set_row_lower(row, -max_order);
+                    model.set_weight(row, class.order, 1.0);
+                    model.set_weight(row, child_vars.order, -1.0);
+                    model.set_weight(row, node_active, -(max_order + 1.0));
                 }
             }
         }
 
+        let node_costs: HashMap<Id, Vec<f64>> = egraph
+            .classes()
+            .map(|class| {
+                let costs = class
+                    .iter()
+                    .map(|node| cost_function.node_cost(egraph, class.id, node))
+                    .collect();
+                (class.id, costs)
+            })
+            .collect();
+
         model.set_obj_sense(Sense::Minimize);
         for class in egraph.classes() {
-            for (node, &node_active) in class.iter().zip(&vars[&class.id].nodes) {
-                model.set_obj_coeff(node_active, cost_function.node_cost(egraph, class.id, node));
+            let costs = &node_costs[&class.id];
+            for (&cost, &node_active) in costs.iter().zip(&vars[&class.id].nodes) {
+                model.set_obj_coeff(node_active, cost);
             }
         }
 
-        dbg!(max_order);
-
         Self {
             egraph,
             model,
             vars,
+            node_costs,
         }
     }
 
@@ -157,7 +186,21 @@ where
     }
 
     /// Extract (potentially multiple) roots
+    ///
+    /// This is just a shortcut for
+    /// [`LpExtractor::solve_multiple_with_report`].
     pub fn solve_multiple(&mut self, roots: &[Id]) -> (RecExpr<L>, Vec<Id>) {
+        let extraction = self.solve_multiple_with_report(roots);
+        (extraction.expr, extraction.roots)
+    }
+
+    /// Extract (potentially multiple) roots, reporting the solver status,
+    /// objective value, and optimality gap.
+    ///
+    /// CBC starts from the solution of a greedy [`Extractor`] using the
+    /// same node costs, so there is a solution to return even if it runs
+    /// out of time.
+    pub fn solve_multiple_with_report(&mut self, roots: &[Id]) -> LpExtraction<L> {
         let egraph = self.egraph;
 
         for class in self.vars.values() {
@@ -169,74 +212,113 @@ where
             self.model.set_col_lower(self.vars[root].active, 1.0);
         }
 
+        self.set_initial_solution(roots);
+
         let solution = self.model.solve();
+        let raw = solution.raw();
         log::info!(
             "CBC status {:?}, {:?}",
-            solution.raw().status(),
-            solution.raw().secondary_status()
+            raw.status(),
+            raw.secondary_status()
         );
 
-        let mut todo: Vec<Id> = roots.iter().map(|id| self.egraph.find(*id)).collect();
-        let mut expr = RecExpr::default();
-        // converts e-class ids to e-node ids
-        let mut ids: HashMap<Id, Id> = HashMap::default();
+        let (expr, root_idxs) = crate::extract::build_multi_root_expr(egraph, roots, |id| {
+            let v = &self.vars[&id];
+            assert!(solution.col(v.active) > 0.0);
+            let node_idx = v.nodes.iter().position(|&n| solution.col(n) > 0.0).unwrap();
+            &egraph[id].nodes[node_idx]
+        });
+
+        assert!(expr.is_dag(), "LpExtract found a cyclic term!: {:?}", expr);
+
+        let objective = raw.obj_value();
+        let best_bound = raw.best_possible_value();
+        let gap = if objective == 0.0 {
+            0.0
+        } else {
+            ((objective - best_bound) / objective.abs()).max(0.0)
+        };
+        LpExtraction {
+            expr,
+            roots: root_idxs,
+            status: raw.status(),
+            secondary_status: raw.secondary_status(),
+            proven_optimal: raw.is_proven_optimal(),
+            objective,
+            best_bound,
+            gap,
+        }
+    }
+
+    /// Warm starts CBC with the solution of a greedy [`Extractor`],
+    /// which is always feasible.
+    fn set_initial_solution(&mut self, roots: &[Id]) {
+        let egraph = self.egraph;
+        let node_costs = NodeCosts::new(egraph, &self.node_costs);
+        let extractor = Extractor::new(egraph, node_costs);
 
+        // the order of a class is its height in the extracted DAG
+        let mut order: HashMap<Id, usize> = HashMap::default();
+        let mut todo: Vec<Id> = roots.iter().map(|&id| egraph.find(id)).collect();
         while let Some(&id) = todo.last() {
-            if ids.contains_key(&id) {
+            if order.contains_key(&id) {
                 todo.pop();
                 continue;
             }
-            let v = &self.vars[&id];
-            assert!(solution.col(v.active) > 0.0);
-            let node_idx = v.nodes.iter().position(|&n| solution.col(n) > 0.0).unwrap();
-            let node = &self.egraph[id].nodes[node_idx];
-            if node.all(|child| ids.contains_key(&child)) {
-                let new_id = expr.add(node.clone().map_children(|i| ids[&self.egraph.find(i)]));
-                ids.insert(id, new_id);
+            let node = extractor.find_best_node(id);
+            let children: Vec<Id> = node.children().iter().map(|&c| egraph.find(c)).collect();
+            if children.iter().all(|c| order.contains_key(c)) {
+                let height = children.iter().map(|c| order[c] + 1).max().unwrap_or(0);
+                order.insert(id, height);
                 todo.pop();
             } else {
-                todo.extend_from_slice(node.children())
+                todo.extend(children);
             }
         }
 
-        let root_idxs = roots.iter().map(|root| ids[root]).collect();
-
-        assert!(expr.is_dag(), "LpExtract found a cyclic term!: {:?}", expr);
-        (expr, root_idxs)
+        for (&id, vars) in &self.vars {
+            let best = order.get(&id).map(|_| extractor.find_best_node(id));
+            self.model
+                .set_initial_solution(vars.active, best.map_or(0.0, |_| 1.0));
+            self.model
+                .set_initial_solution(vars.order, order.get(&id).map_or(0.0, |&o| o as f64));
+            for (node, &node_active) in egraph[id].iter().zip(&vars.nodes) {
+                let picked = best == Some(node);
+                self.model
+                    .set_initial_solution(node_active, if picked { 1.0 } else { 0.0 });
+            }
+        }
     }
 }
 
-fn find_cycles<L, N>(egraph: &EGraph<L, N>, mut f: impl FnMut(Id, usize))
-where
-    L: Language,
-    N: Analysis<L>,
-{
-    enum Color {
-        White,
-        Gray,
-        Black,
-    }
-    type Enter = bool;
+/// A [`CostFunction`] that sums up the node costs of an [`LpCostFunction`],
+/// for the greedy warm start.
+struct NodeCosts<L> {
+    costs: HashMap<L, f64>,
+}
 
-    let mut color: HashMap<Id, Color> = egraph.classes().map(|c| (c.id, Color::White)).collect();
-    let mut stack: Vec<(Enter, Id)> = egraph.classes().map(|c| (true, c.id)).collect();
+impl<L: Language> NodeCosts<L> {
+    fn new<N: Analysis<L>>(egraph: &EGraph<L, N>, node_costs: &HashMap<Id, Vec<f64>>) -> Self {
+        let costs = egraph
+            .classes()
+            .flat_map(|class| {
+                class
+                    .iter()
+                    .cloned()
+                    .zip(node_costs[&class.id].iter().copied())
+            })
+            .collect();
+        Self { costs }
+    }
+}
 
-    while let Some((enter, id)) = stack.pop() {
-        if enter {
-            *color.get_mut(&id).unwrap() = Color::Gray;
-            stack.push((false, id));
-            for (i, node) in egraph[id].iter().enumerate() {
-                for child in node.children() {
-                    match &color[child] {
-                        Color::White => stack.push((true, *child)),
-                        Color::Gray => f(id, i),
-                        Color::Black => (),
-                    }
-                }
-            }
-        } else {
-            *color.get_mut(&id).unwrap() = Color::Black;
-        }
+impl<L: Language> CostFunction<L> for NodeCosts<L> {
+    type Cost = f64;
+    fn cost<C>(&mut self, enode: &L, mut costs: C) -> Self::Cost
+    where
+        C: FnMut(Id) -> Self::Cost,
+    {
+        enode.fold(self.costs[enode], |sum, id| sum + costs(id))
     }
 }
 
@@ -260,4 +342,25 @@ mod tests {
         assert_eq!(exp.as_ref().len(), 4);
         assert_eq!(ids.len(), 2);
     }
+
+    #[test]
+    fn lp_extract_avoidable_cycle() {
+        let mut egraph = EGraph::<S, ()>::default();
+        // a = (f b), b = (g a) = y, so (f b) is on a cycle but still usable
+        let a = egraph.add_expr(&"(f y)".parse().unwrap());
+        let y = egraph.lookup(S::leaf("y")).unwrap();
+        let ga = egraph.add(S::new("g", vec![a]));
+        egraph.union(ga, y);
+        let expensive = egraph.add_expr(&"(k (k (k (k z))))".parse().unwrap());
+        egraph.union(a, expensive);
+        egraph.rebuild();
+
+        let mut ext = LpExtractor::new(&egraph, AstSize);
+        ext.timeout(10.0);
+        let extraction = ext.solve_multiple_with_report(&[a]);
+        assert_eq!(extraction.expr.to_string(), "(f y)");
+        assert!(extraction.proven_optimal);
+        assert_eq!(extraction.objective, 2.0);
+        assert_eq!(extraction.gap, 0.0);
+    }
 }
@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::str::FromStr;
 use thiserror::Error;
 
@@ -20,11 +21,16 @@ use crate::*;
 /// When applying a mulitpattern, patterns bound a variable occuring in the
 /// searcher are unioned with that e-class.
 ///
-/// Multipatterns currently do not support the explanations feature.
+/// When explanations are enabled, each union made by a multipattern applier
+/// records the terms matched by each pattern of the searcher (which must also be
+/// a multipattern) as premises, see [`TreeTerm::premises`].
 #[derive(Debug, PartialEq, Clone)]
 pub struct MultiPattern<L> {
     asts: Vec<(Var, PatternAst<L>)>,
     program: machine::Program<L>,
+    /// For each pattern, a program that matches it first,
+    /// used by [`Searcher::search_since`].
+    rotated_programs: Vec<machine::Program<L>>,
 }
 
 impl<L: Language> MultiPattern<L> {
@@ -53,7 +59,19 @@ impl<L: Language> MultiPattern<L> {
     /// ```
     pub fn new(asts: Vec<(Var, PatternAst<L>)>) -> Self {
         let program = machine::Program::compile_from_multi_pat(&asts);
-        Self { asts, program }
+        let rotated_programs = (0..asts.len())
+            .map(|i| {
+                let mut rotated = asts.clone();
+                let first = rotated.remove(i);
+                rotated.insert(0, first);
+                machine::Program::compile_from_multi_pat(&rotated)
+            })
+            .collect();
+        Self {
+            asts,
+            program,
+            rotated_programs,
+        }
     }
 }
 
@@ -101,6 +119,10 @@ impl<L: Language + FromOp> FromStr for MultiPattern<L> {
 }
 
 impl<L: Language, A: Analysis<L>> Searcher<L, A> for MultiPattern<L> {
+    fn get_multi_pattern_ast(&self) -> Option<&[(Var, PatternAst<L>)]> {
+        Some(&self.asts)
+    }
+
     fn search_eclass(&self, egraph: &EGraph<L, A>, eclass: Id) -> Option<SearchMatches<L>> {
         let substs = self.program.run(egraph, eclass);
         if substs.is_empty() {
@@ -110,10 +132,48 @@ impl<L: Language, A: Analysis<L>> Searcher<L, A> for MultiPattern<L> {
                 eclass,
                 substs,
                 ast: None,
+                multi_ast: Some(Cow::Borrowed(&self.asts)),
             })
         }
     }
 
+    /// A new match for a multipattern must have at least one of its patterns
+    /// matching in the changed part of the e-graph, so this runs, for each
+    /// pattern, a program that matches that pattern first against only the
+    /// changed e-classes.
+    fn search_since(&self, egraph: &EGraph<L, A>, since: usize) -> Vec<SearchMatches<L>> {
+        if since == 0 {
+            return self.search(egraph);
+        }
+        let mut seen = HashSet::default();
+        let mut matches = vec![];
+        for ((_, ast), program) in self.asts.iter().zip(&self.rotated_programs) {
+            for eclass in egraph.classes_changed_since(since, ast.search_depth()) {
+                if egraph.is_cancelled() {
+                    break;
+                }
+                let substs: Vec<Subst> = program
+                    .run(egraph, eclass)
+                    .into_iter()
+                    .filter(|subst| {
+                        let mut key = subst.vec.clone();
+                        key.sort_unstable();
+                        seen.insert(key)
+                    })
+                    .collect();
+                if !substs.is_empty() {
+                    matches.push(SearchMatches {
+                        eclass,
+                        substs,
+                        ast: None,
+                        multi_ast: Some(Cow::Borrowed(&self.asts)),
+                    });
+                }
+            }
+        }
+        matches
+    }
+
     fn vars(&self) -> Vec<Var> {
         let mut vars = vec![];
         for (v, pat) in &self.asts {
@@ -131,6 +191,10 @@ impl<L: Language, A: Analysis<L>> Searcher<L, A> for MultiPattern<L> {
 }
 
 impl<L: Language, A: Analysis<L>> Applier<L, A> for MultiPattern<L> {
+    fn get_multi_pattern_ast(&self) -> Option<&[(Var, PatternAst<L>)]> {
+        Some(&self.asts)
+    }
+
     fn apply_one(
         &self,
         _egraph: &mut EGraph<L, A>,
@@ -146,12 +210,18 @@ impl<L: Language, A: Analysis<L>> Applier<L, A> for MultiPattern<L> {
         &self,
         egraph: &mut EGraph<L, A>,
         matches: &[SearchMatches<L>],
-        _rule_name: Symbol,
+        rule_name: Symbol,
     ) -> Vec<Id> {
-        // TODO explanations?
+        if egraph.are_explanations_enabled() {
+            return self.apply_matches_explained(egraph, matches, rule_name);
+        }
+
         // the ids returned are kinda garbage
         let mut added = vec![];
         for mat in matches {
+            if egraph.is_cancelled() {
+                break;
+            }
             for subst in &mat.substs {
                 let mut subst = subst.clone();
                 let mut id_buf = vec![];
@@ -190,6 +260,57 @@ impl<L: Language, A: Analysis<L>> Applier<L, A> for MultiPattern<L> {
     }
 }
 
+impl<L: Language> MultiPattern<L> {
+    /// Like [`Applier::apply_matches`], but recording the terms matched by
+    /// the searcher as the premises of each union.
+    fn apply_matches_explained<A: Analysis<L>>(
+        &self,
+        egraph: &mut EGraph<L, A>,
+        matches: &[SearchMatches<L>],
+        rule_name: Symbol,
+    ) -> Vec<Id> {
+        let mut added = vec![];
+        for mat in matches {
+            if egraph.is_cancelled() {
+                break;
+            }
+            let searcher_asts = mat.multi_ast.as_ref().expect(
+                "Multipattern appliers need a multipattern searcher to produce explanations.",
+            );
+            for subst in &mat.substs {
+                // each term matched by the searcher, with the e-class bound to its variable
+                let premises: Vec<(Id, Id)> = searcher_asts
+                    .iter()
+                    .map(|(v, p)| (subst[*v], egraph.add_instantiation_internal(p, subst)))
+                    .collect();
+
+                let mut subst = subst.clone();
+                for (i, (v, p)) in self.asts.iter().enumerate() {
+                    let id1 = match subst.get(*v) {
+                        Some(&id2) => {
+                            egraph
+                                .union_instantiation_with_premises(
+                                    id2,
+                                    p,
+                                    &subst,
+                                    rule_name,
+                                    premises.clone(),
+                                )
+                                .0
+                        }
+                        None => egraph.add_instantiation_internal(p, &subst),
+                    };
+                    subst.insert(*v, id1);
+                    if i == 0 {
+                        added.push(egraph.find(id1))
+                    }
+                }
+            }
+        }
+        added
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{SymbolLang as S, *};
@@ -238,6 +359,48 @@ mod tests {
         assert_eq!(n_matches("?x = (f a b), ?x = (f a c)"), 1);
     }
 
+    #[test]
+    fn multi_patterns_since() {
+        crate::init_logger();
+        let mut egraph = EGraph::default();
+        egraph.add_string("(f a)");
+        egraph.add_string("(g b)");
+        egraph.rebuild();
+
+        let mp: MultiPattern<S> = "?x = (f ?a), ?y = (g ?a)".parse().unwrap();
+        let n_matches_since = |egraph: &EGraph, since| -> usize {
+            mp.search_since(egraph, since)
+                .iter()
+                .map(|m| m.substs.len())
+                .sum()
+        };
+        assert_eq!(mp.n_matches(&egraph), 0);
+
+        // a new match from a change under the second pattern
+        let since = egraph.new_generation();
+        egraph.add_string("(g a)");
+        egraph.rebuild();
+        assert_eq!(n_matches_since(&egraph, since), 1);
+
+        // a new match from a change under the first pattern
+        let since = egraph.new_generation();
+        egraph.add_string("(f b)");
+        egraph.rebuild();
+        assert_eq!(n_matches_since(&egraph, since), 1);
+        assert_eq!(mp.n_matches(&egraph), 2);
+
+        // matches found through both patterns are only reported once
+        let since = egraph.new_generation();
+        let a = egraph.add_string("a");
+        let b = egraph.add_string("b");
+        egraph.union(a, b);
+        egraph.rebuild();
+        assert_eq!(n_matches_since(&egraph, since), 1);
+
+        let since = egraph.new_generation();
+        assert_eq!(n_matches_since(&egraph, since), 0);
+    }
+
     #[test]
     fn unbound_rhs() {
         let mut egraph = EGraph::default();
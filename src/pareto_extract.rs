@@ -0,0 +1,343 @@
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+
+use crate::util::{HashMap, HashSet};
+use crate::{Analysis, CostFunction, EGraph, Id, Language, RecExpr};
+
+/** A cost with several components that can be compared by Pareto dominance.
+
+A cost _dominates_ another if it is no worse in every component and better
+in at least one. This is implemented for arrays and for pairs and triples of
+[`PartialOrd`] components.
+
+```
+use egg::ParetoCost;
+assert!([1, 2].dominates(&[1, 3]));
+assert!(!(1, 3).dominates(&(2, 1)));
+assert!(!(2, 1).dominates(&(1, 3)));
+assert!(!(1.0, 2.0).dominates(&(1.0, 2.0)));
+```
+**/
+pub trait ParetoCost {
+    /// Whether `self` is no worse than `other` in every component and
+    /// better in at least one.
+    fn dominates(&self, other: &Self) -> bool;
+}
+
+/// Whether the orderings of the components are all `Less` or `Equal`,
+/// and at least one is `Less`.
+fn compare_components(pairs: impl IntoIterator<Item = Option<Ordering>>) -> bool {
+    let mut better = false;
+    for ordering in pairs {
+        match ordering.expect("costs must be comparable") {
+            Ordering::Less => better = true,
+            Ordering::Equal => (),
+            Ordering::Greater => return false,
+        }
+    }
+    better
+}
+
+impl<T: PartialOrd, const N: usize> ParetoCost for [T; N] {
+    fn dominates(&self, other: &Self) -> bool {
+        compare_components(self.iter().zip(other).map(|(a, b)| a.partial_cmp(b)))
+    }
+}
+
+impl<A: PartialOrd, B: PartialOrd> ParetoCost for (A, B) {
+    fn dominates(&self, other: &Self) -> bool {
+        compare_components([self.0.partial_cmp(&other.0), self.1.partial_cmp(&other.1)])
+    }
+}
+
+impl<A: PartialOrd, B: PartialOrd, C: PartialOrd> ParetoCost for (A, B, C) {
+    fn dominates(&self, other: &Self) -> bool {
+        compare_components([
+            self.0.partial_cmp(&other.0),
+            self.1.partial_cmp(&other.1),
+            self.2.partial_cmp(&other.2),
+        ])
+    }
+}
+
+/** Extracting the [Pareto frontier](https://en.wikipedia.org/wiki/Pareto_front)
+of terms from each e-class of an [`EGraph`], for costs with several
+components.
+
+Where an [`Extractor`](crate::Extractor) has to collapse the cost of a term
+into one number, a `ParetoExtractor` keeps every term whose
+[`ParetoCost`] is not dominated by another one of the same e-class.
+You can then pick one from the frontier of the root, e.g. the lowest latency
+among the terms under a code size budget.
+
+The [`CostFunction`] must be _monotonic_ in every component, like for an
+[`Extractor`](crate::Extractor).
+Children in the same e-class are always extracted as the same term.
+Note that the frontier of an e-node is built from every combination of the
+frontiers of its children, so this can get expensive for e-nodes with many
+children.
+
+```
+use egg::*;
+
+// (latency, size)
+struct LatencySize;
+impl CostFunction<SymbolLang> for LatencySize {
+    type Cost = (usize, usize);
+    fn cost<C>(&mut self, enode: &SymbolLang, mut costs: C) -> Self::Cost
+    where
+        C: FnMut(Id) -> Self::Cost,
+    {
+        let latency = match enode.op.as_str() {
+            "*" => 4,
+            "<<" => 1,
+            _ => 2,
+        };
+        enode.fold((latency, 1), |(l, s), id| {
+            let (cl, cs) = costs(id);
+            (l.max(cl + latency), s + cs)
+        })
+    }
+}
+
+let mut egraph = EGraph::<SymbolLang, ()>::default();
+let a = egraph.add_expr(&"(* x 4)".parse().unwrap());
+let b = egraph.add_expr(&"(<< (<< x 1) 1)".parse().unwrap());
+egraph.union(a, b);
+egraph.rebuild();
+
+let extractor = ParetoExtractor::new(&egraph, LatencySize);
+let frontier: Vec<_> = extractor
+    .frontier(a)
+    .into_iter()
+    .map(|(cost, expr)| (cost, expr.to_string()))
+    .collect();
+assert_eq!(frontier, vec![
+    ((6, 3), "(* x 4)".to_string()),
+    ((4, 5), "(<< (<< x 1) 1)".to_string()),
+]);
+
+// pick the fastest one
+let (cost, _) = extractor.find_best_by(a, |x, y| x.0.cmp(&y.0));
+assert_eq!(cost, (4, 5));
+```
+**/
+#[derive(Debug)]
+pub struct ParetoExtractor<'a, CF: CostFunction<L>, L: Language, N: Analysis<L>> {
+    cost_function: CF,
+    candidates: Vec<Candidate<CF::Cost, L>>,
+    frontiers: HashMap<Id, Vec<usize>>,
+    egraph: &'a EGraph<L, N>,
+}
+
+/// A term on the frontier of an e-class: an e-node and, for each of its
+/// distinct child e-classes, the candidate to use for it.
+/// Candidates are never modified, so the indices stay valid even when
+/// the child drops them from its frontier.
+#[derive(Debug, Clone)]
+struct Candidate<C, L> {
+    cost: C,
+    node: L,
+    children: Vec<(Id, usize)>,
+}
+
+impl<'a, CF, L, N> ParetoExtractor<'a, CF, L, N>
+where
+    CF: CostFunction<L>,
+    CF::Cost: ParetoCost,
+    L: Language,
+    N: Analysis<L>,
+{
+    /// Create a new `ParetoExtractor` given an `EGraph` and a
+    /// `CostFunction`.
+    ///
+    /// Like [`Extractor::new`](crate::Extractor::new), this does all the
+    /// work on creation.
+    pub fn new(egraph: &'a EGraph<L, N>, cost_function: CF) -> Self {
+        let mut extractor = ParetoExtractor {
+            cost_function,
+            candidates: vec![],
+            frontiers: HashMap::default(),
+            egraph,
+        };
+        extractor.find_frontiers();
+        extractor
+    }
+
+    /// The non-dominated terms of the given e-class, in the order they
+    /// were found.
+    pub fn frontier(&self, eclass: Id) -> Vec<(CF::Cost, RecExpr<L>)> {
+        self.frontier_candidates(eclass)
+            .iter()
+            .map(|&i| (self.candidates[i].cost.clone(), self.build_expr(i)))
+            .collect()
+    }
+
+    /// The costs of the non-dominated terms of the given e-class,
+    /// in the same order as [`frontier`](ParetoExtractor::frontier).
+    pub fn frontier_costs(&self, eclass: Id) -> Vec<CF::Cost> {
+        self.frontier_candidates(eclass)
+            .iter()
+            .map(|&i| self.candidates[i].cost.clone())
+            .collect()
+    }
+
+    /// Pick the term on the frontier of the given e-class with the
+    /// smallest cost according to `compare`, e.g. a weighted sum of the
+    /// components or a lexicographic order.
+    ///
+    /// Panics if the e-class has no term.
+    pub fn find_best_by(
+        &self,
+        eclass: Id,
+        mut compare: impl FnMut(&CF::Cost, &CF::Cost) -> Ordering,
+    ) -> (CF::Cost, RecExpr<L>) {
+        let &best = self
+            .frontier_candidates(eclass)
+            .iter()
+            .min_by(|&&a, &&b| compare(&self.candidates[a].cost, &self.candidates[b].cost))
+            .unwrap_or_else(|| panic!("Can't extract, eclass {} has no term", eclass));
+        (self.candidates[best].cost.clone(), self.build_expr(best))
+    }
+
+    fn frontier_candidates(&self, eclass: Id) -> &[usize] {
+        let eclass = self.egraph.find(eclass);
+        self.frontiers.get(&eclass).map_or(&[], |f| f.as_slice())
+    }
+
+    fn build_expr(&self, candidate: usize) -> RecExpr<L> {
+        let mut expr = RecExpr::default();
+        self.build_expr_rec(candidate, &mut expr, &mut HashMap::default());
+        expr
+    }
+
+    fn build_expr_rec(
+        &self,
+        candidate: usize,
+        expr: &mut RecExpr<L>,
+        built: &mut HashMap<usize, Id>,
+    ) -> Id {
+        if let Some(&id) = built.get(&candidate) {
+            return id;
+        }
+        let Candidate { node, children, .. } = &self.candidates[candidate];
+        let node = node.clone().map_children(|child| {
+            let child = self.egraph.find(child);
+            let &(_, c) = children.iter().find(|(id, _)| *id == child).unwrap();
+            self.build_expr_rec(c, expr, built)
+        });
+        let id = expr.add(node);
+        built.insert(candidate, id);
+        id
+    }
+
+    fn find_frontiers(&mut self) {
+        let egraph = self.egraph;
+        let mut todo: VecDeque<Id> = egraph.classes().map(|c| c.id).collect();
+        let mut queued: HashSet<Id> = todo.iter().copied().collect();
+
+        while let Some(id) = todo.pop_front() {
+            queued.remove(&id);
+            if self.update_frontier(id) {
+                for (_, parent) in &egraph[id].parents {
+                    let parent = egraph.find(*parent);
+                    if queued.insert(parent) {
+                        todo.push_back(parent);
+                    }
+                }
+            }
+        }
+
+        for class in egraph.classes() {
+            if !self.frontiers.contains_key(&class.id) {
+                log::warn!(
+                    "Failed to compute cost for eclass {}: {:?}",
+                    class.id,
+                    class.nodes
+                )
+            }
+        }
+    }
+
+    /// Adds every combination of the children's frontiers of every e-node
+    /// that isn't dominated to the frontier of the e-class.
+    /// Returns whether the frontier changed.
+    fn update_frontier(&mut self, id: Id) -> bool {
+        let egraph = self.egraph;
+        let mut changed = false;
+        for node in egraph[id].iter() {
+            let mut children: Vec<Id> = vec![];
+            for &child in node.children() {
+                let child = egraph.find(child);
+                if !children.contains(&child) {
+                    children.push(child);
+                }
+            }
+            let child_frontiers: Vec<Vec<usize>> = match children
+                .iter()
+                .map(|c| self.frontiers.get(c).cloned())
+                .collect()
+            {
+                Some(frontiers) => frontiers,
+                None => continue,
+            };
+
+            // go through every combination like an odometer
+            let mut choice = vec![0; children.len()];
+            loop {
+                let picked: Vec<(Id, usize)> = children
+                    .iter()
+                    .zip(&choice)
+                    .zip(&child_frontiers)
+                    .map(|((&c, &i), frontier)| (c, frontier[i]))
+                    .collect();
+                changed |= self.insert(id, node, picked);
+
+                let mut digit = 0;
+                while digit < choice.len() {
+                    choice[digit] += 1;
+                    if choice[digit] < child_frontiers[digit].len() {
+                        break;
+                    }
+                    choice[digit] = 0;
+                    digit += 1;
+                }
+                if digit == choice.len() {
+                    break;
+                }
+            }
+        }
+        changed
+    }
+
+    /// Inserts the candidate into the frontier of the e-class, unless it's
+    /// dominated by or costs the same as a term already there.
+    fn insert(&mut self, id: Id, node: &L, children: Vec<(Id, usize)>) -> bool {
+        let candidates = &self.candidates;
+        let egraph = self.egraph;
+        let cost = self.cost_function.cost(node, |child| {
+            let child = egraph.find(child);
+            let &(_, c) = children.iter().find(|(id, _)| *id == child).unwrap();
+            candidates[c].cost.clone()
+        });
+
+        let frontier = self.frontiers.entry(id).or_default();
+        let redundant = frontier.iter().any(|&i| {
+            let other = &candidates[i].cost;
+            other.dominates(&cost) || other.partial_cmp(&cost) == Some(Ordering::Equal)
+        });
+        if redundant {
+            return false;
+        }
+
+        frontier.retain(|&i| !cost.dominates(&candidates[i].cost));
+        frontier.push(self.candidates.len());
+        self.candidates.push(Candidate {
+            cost,
+            node: node.clone(),
+            children,
+        });
+        true
+    }
+}
@@ -98,6 +98,29 @@ impl<L: Language> PatternAst<L> {
 
         new
     }
+
+    /// Returns how many levels of e-classes below the root a search for
+    /// this pattern looks at, i.e. the height of the pattern.
+    /// The e-classes bound to variables count too, since conditions
+    /// often look at their analysis data.
+    pub(crate) fn search_depth(&self) -> usize {
+        let mut heights: Vec<usize> = Vec::with_capacity(self.as_ref().len());
+        for n in self.as_ref() {
+            let height = match n {
+                ENodeOrVar::ENode(e) => {
+                    1 + e
+                        .children()
+                        .iter()
+                        .map(|&c| heights[usize::from(c)])
+                        .max()
+                        .unwrap_or(0)
+                }
+                ENodeOrVar::Var(_) => 1,
+            };
+            heights.push(height);
+        }
+        heights.last().map_or(0, |h| h.saturating_sub(1))
+    }
 }
 
 impl<L: Language> Pattern<L> {
@@ -255,33 +278,76 @@ pub struct SearchMatches<'a, L: Language> {
     pub substs: Vec<Subst>,
     /// Optionally, an ast for the matches used in proof production.
     pub ast: Option<Cow<'a, PatternAst<L>>>,
+    /// Optionally, the asts bound to each variable of a [`MultiPattern`],
+    /// used in proof production.
+    #[allow(clippy::type_complexity)]
+    pub multi_ast: Option<Cow<'a, [(Var, PatternAst<L>)]>>,
 }
 
-impl<L: Language, A: Analysis<L>> Searcher<L, A> for Pattern<L> {
-    fn get_pattern_ast(&self) -> Option<&PatternAst<L>> {
-        Some(&self.ast)
-    }
-
-    fn search(&self, egraph: &EGraph<L, A>) -> Vec<SearchMatches<L>> {
+impl<L: Language> Pattern<L> {
+    /// The e-classes that may contain a match, or a new one since the
+    /// given generation.
+    fn candidates<'e, A: Analysis<L>>(
+        &self,
+        egraph: &'e EGraph<L, A>,
+        since: Option<usize>,
+    ) -> Box<dyn Iterator<Item = Id> + 'e> {
+        let changed = match since {
+            Some(since) if since != 0 => {
+                Some(egraph.classes_changed_since(since, self.ast.search_depth()))
+            }
+            _ => None,
+        };
         match self.ast.as_ref().last().unwrap() {
             ENodeOrVar::ENode(e) => {
                 #[allow(enum_intrinsics_non_enums)]
                 let key = std::mem::discriminant(e);
-                match egraph.classes_by_op.get(&key) {
-                    None => vec![],
-                    Some(ids) => ids
-                        .iter()
-                        .filter_map(|&id| self.search_eclass(egraph, id))
-                        .collect(),
+                match (egraph.classes_by_op.get(&key), changed) {
+                    (None, _) => Box::new(std::iter::empty()),
+                    (Some(ids), None) => Box::new(ids.iter().copied()),
+                    (Some(ids), Some(changed)) => {
+                        Box::new(changed.into_iter().filter(move |id| ids.contains(id)))
+                    }
                 }
             }
-            ENodeOrVar::Var(_) => egraph
-                .classes()
-                .filter_map(|e| self.search_eclass(egraph, e.id))
-                .collect(),
+            ENodeOrVar::Var(_) => match changed {
+                Some(changed) => Box::new(changed.into_iter()),
+                None => Box::new(egraph.classes().map(|e| e.id)),
+            },
         }
     }
 
+    /// Searches the [`candidates`](Pattern::candidates), stopping early if
+    /// the e-graph is cancelled.
+    fn search_candidates<A: Analysis<L>>(
+        &self,
+        egraph: &EGraph<L, A>,
+        since: Option<usize>,
+    ) -> Vec<SearchMatches<L>> {
+        self.candidates(egraph, since)
+            .take_while(|_| !egraph.is_cancelled())
+            .filter_map(|id| self.search_eclass(egraph, id))
+            .collect()
+    }
+}
+
+impl<L: Language, A: Analysis<L>> Searcher<L, A> for Pattern<L> {
+    fn get_pattern_ast(&self) -> Option<&PatternAst<L>> {
+        Some(&self.ast)
+    }
+
+    fn search(&self, egraph: &EGraph<L, A>) -> Vec<SearchMatches<L>> {
+        self.search_candidates(egraph, None)
+    }
+
+    fn search_since(&self, egraph: &EGraph<L, A>, since: usize) -> Vec<SearchMatches<L>> {
+        self.search_candidates(egraph, Some(since))
+    }
+
+    fn eclasses_to_search(&self, egraph: &EGraph<L, A>, since: Option<usize>) -> Option<Vec<Id>> {
+        Some(self.candidates(egraph, since).collect())
+    }
+
     fn search_eclass(&self, egraph: &EGraph<L, A>, eclass: Id) -> Option<SearchMatches<L>> {
         let substs = self.program.run(egraph, eclass);
         if substs.is_empty() {
@@ -292,6 +358,7 @@ impl<L: Language, A: Analysis<L>> Searcher<L, A> for Pattern<L> {
                 eclass,
                 substs,
                 ast,
+                multi_ast: None,
             })
         }
     }
@@ -320,6 +387,9 @@ where
         let ast = self.ast.as_ref();
         let mut id_buf = vec![0.into(); ast.len()];
         for mat in matches {
+            if egraph.is_cancelled() {
+                break;
+            }
             let sast = mat.ast.as_ref().map(|cow| cow.as_ref());
             for subst in &mat.substs {
                 let did_something;
@@ -467,4 +537,60 @@ mod tests {
         assert_eq!(n_matches("(f ?x (g ?x))))"), 1);
         assert_eq!(n_matches("(h ?x 0 0)"), 1);
     }
+
+    #[test]
+    fn search_since() {
+        crate::init_logger();
+        let mut egraph = EGraph::default();
+        for i in 0..10 {
+            egraph.add_expr(&format!("(f (h {}))", i).parse().unwrap());
+        }
+        let g = egraph.add_expr(&"(g x)".parse().unwrap());
+        egraph.rebuild();
+
+        let pat: Pattern<S> = "(f (h (g ?x)))".parse().unwrap();
+        assert_eq!(pat.n_matches(&egraph), 0);
+        let since = egraph.new_generation();
+
+        // make (f (h 3)) match by merging deep below it
+        let three = egraph.lookup(S::leaf("3")).unwrap();
+        egraph.union(three, g);
+        egraph.rebuild();
+
+        let f3 = egraph.lookup_expr(&"(f (h 3))".parse().unwrap()).unwrap();
+        let matches = pat.search_since(&egraph, since);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].eclass, f3);
+
+        // only the changed classes and their ancestors are searched
+        assert_eq!(egraph.classes_changed_since(since, 2).len(), 3);
+        let since = egraph.new_generation();
+        assert!(pat.search_since(&egraph, since).is_empty());
+        assert_eq!(pat.search_since(&egraph, 0).len(), 1);
+    }
+
+    #[test]
+    fn cancelled_search() {
+        crate::init_logger();
+        let mut egraph = EGraph::default();
+        egraph.add_expr(&"(+ (+ a b) (+ c d))".parse().unwrap());
+        egraph.rebuild();
+
+        let pat: Pattern<S> = "(+ ?x ?y)".parse().unwrap();
+        let matches = pat.search(&egraph);
+        assert_eq!(matches.len(), 3);
+
+        let token = CancellationToken::new();
+        egraph.cancellation_token = Some(token.clone());
+        assert_eq!(pat.search(&egraph).len(), 3);
+        token.cancel();
+        assert!(egraph.is_cancelled());
+        assert!(pat.search(&egraph).is_empty());
+
+        let swap: Pattern<S> = "(+ ?y ?x)".parse().unwrap();
+        assert!(swap
+            .apply_matches(&mut egraph, &matches, "swap".into())
+            .is_empty());
+        assert_eq!(egraph.total_size(), 7);
+    }
 }
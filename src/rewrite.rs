@@ -13,7 +13,6 @@ use crate::*;
 /// It additionally stores a name used to refer to the rewrite and a
 /// long name used for debugging.
 ///
-#[derive(Clone)]
 #[non_exhaustive]
 pub struct Rewrite<L, N> {
     /// The name of the rewrite.
@@ -24,6 +23,17 @@ pub struct Rewrite<L, N> {
     pub applier: Arc<dyn Applier<L, N> + Sync + Send>,
 }
 
+// Not derived, since that would require `L: Clone` and `N: Clone`.
+impl<L, N> Clone for Rewrite<L, N> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name,
+            searcher: self.searcher.clone(),
+            applier: self.applier.clone(),
+        }
+    }
+}
+
 impl<L, N> Debug for Rewrite<L, N>
 where
     L: Language + Display + 'static,
@@ -84,6 +94,13 @@ impl<L: Language, N: Analysis<L>> Rewrite<L, N> {
         self.searcher.search(egraph)
     }
 
+    /// Call [`search_since`] on the [`Searcher`].
+    ///
+    /// [`search_since`]: Searcher::search_since()
+    pub fn search_since(&self, egraph: &EGraph<L, N>, since: usize) -> Vec<SearchMatches<L>> {
+        self.searcher.search_since(egraph, since)
+    }
+
     /// Call [`apply_matches`] on the [`Applier`].
     ///
     /// [`apply_matches`]: Applier::apply_matches()
@@ -138,10 +155,48 @@ where
     fn search(&self, egraph: &EGraph<L, N>) -> Vec<SearchMatches<L>> {
         egraph
             .classes()
+            .take_while(|_| !egraph.is_cancelled())
             .filter_map(|e| self.search_eclass(egraph, e.id))
             .collect()
     }
 
+    /// Search the parts of the [`EGraph`] that changed since the given
+    /// [generation](EGraph::generation), returning at least every match
+    /// that is new since then.
+    /// Matches that were already there may be returned again.
+    ///
+    /// This allows semi-naive matching; see
+    /// [`Runner::with_incremental_search`].
+    /// The default implementation just calls [`search`], which is always
+    /// correct, just not incremental.
+    /// [`Pattern`] and [`MultiPattern`] use
+    /// [`EGraph::classes_changed_since`] to only search the e-classes
+    /// that might contain a new match.
+    ///
+    /// [`search`]: Searcher::search
+    fn search_since(&self, egraph: &EGraph<L, N>, since: usize) -> Vec<SearchMatches<L>> {
+        let _ = since;
+        self.search(egraph)
+    }
+
+    /// Returns the e-classes that [`search`] (or [`search_since`], if
+    /// `since` is given) calls [`search_eclass`] on, in order, if that is
+    /// all they do.
+    ///
+    /// This lets the [`Runner`] split the search of a single rule across
+    /// threads when [parallel search](Runner::with_parallel_search) is
+    /// enabled.
+    /// The default implementation returns `None`, so the rule is searched
+    /// on one thread with [`search`] or [`search_since`].
+    ///
+    /// [`search`]: Searcher::search
+    /// [`search_since`]: Searcher::search_since
+    /// [`search_eclass`]: Searcher::search_eclass
+    fn eclasses_to_search(&self, egraph: &EGraph<L, N>, since: Option<usize>) -> Option<Vec<Id>> {
+        let _ = (egraph, since);
+        None
+    }
+
     /// Returns the number of matches in the e-graph
     fn n_matches(&self, egraph: &EGraph<L, N>) -> usize {
         self.search(egraph).iter().map(|m| m.substs.len()).sum()
@@ -152,6 +207,12 @@ where
         None
     }
 
+    /// For multipatterns, return the asts bound to each variable directly
+    /// as a reference
+    fn get_multi_pattern_ast(&self) -> Option<&[(Var, PatternAst<L>)]> {
+        None
+    }
+
     /// Returns a list of the variables bound by this Searcher
     fn vars(&self) -> Vec<Var>;
 }
@@ -282,6 +343,9 @@ where
     ) -> Vec<Id> {
         let mut added = vec![];
         for mat in matches {
+            if egraph.is_cancelled() {
+                break;
+            }
             let ast = if egraph.are_explanations_enabled() {
                 mat.ast.as_ref().map(|cow| cow.as_ref())
             } else {
@@ -300,6 +364,11 @@ where
         None
     }
 
+    /// For multipatterns, get the asts bound to each variable directly as a reference.
+    fn get_multi_pattern_ast(&self) -> Option<&[(Var, PatternAst<L>)]> {
+        None
+    }
+
     /// Apply a single substitition.
     ///
     /// An [`Applier`] should add things and union them with `eclass`.
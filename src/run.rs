@@ -1,4 +1,6 @@
 use std::fmt::{self, Debug, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use log::*;
 
@@ -49,12 +51,24 @@ Here are some of the things [`Runner`] does for you:
   If this limit is hit, it stops with
   [`StopReason::NodeLimit`].
 
+- Memory limit
+
+  You can set a upper limit on the (approximate) memory used by the egraph.
+  If this limit is hit, it stops with
+  [`StopReason::MemoryLimit`].
+
 - Time limit
 
   You can set a time limit on the runner.
   If this limit is hit, it stops with
   [`StopReason::TimeLimit`].
 
+- Goals
+
+  You can give the runner goals, like two expressions becoming equivalent.
+  Once one of them is reached, it stops with
+  [`StopReason::GoalReached`].
+
 - Rule scheduling
 
   Some rules enable themselves, blowing up the [`EGraph`] and
@@ -150,14 +164,30 @@ pub struct Runner<L: Language, N: Analysis<L>, IterData = ()> {
     /// [`with_hook`](Runner::with_hook()) method, in insertion order.
     #[allow(clippy::type_complexity)]
     pub hooks: Vec<Box<dyn FnMut(&mut Self) -> Result<(), String>>>,
+    /// A [`PhaseReport`] for each run of a [`Phase`] by
+    /// [`run_phases`](Runner::run_phases()), in the order they ran.
+    pub phase_reports: Vec<PhaseReport>,
 
     // limits
     iter_limit: usize,
     node_limit: usize,
+    memory_limit: usize,
     time_limit: Duration,
 
+    goals: Vec<Goal<L>>,
+    retain_reachable: bool,
+    incremental_search: bool,
+    parallel_search: Option<ParallelSearchFn<L, N>>,
+    /// For each rule, the generation of the e-graph when it was last searched.
+    searched_at: IndexMap<Symbol, usize>,
     start_time: Option<Instant>,
+    /// When the runner last stopped, so the time until it is resumed
+    /// doesn't count towards the time limit.
+    stopped_at: Option<Instant>,
+    cancellation_token: Option<CancellationToken>,
     scheduler: Box<dyn RewriteScheduler<L, N>>,
+    phases: Vec<Phase<L, N>>,
+    phase_rounds: usize,
 }
 
 impl<L, N> Default for Runner<L, N, ()>
@@ -184,11 +214,22 @@ where
             roots,
             stop_reason,
             hooks,
+            phase_reports,
             iter_limit,
             node_limit,
+            memory_limit,
             time_limit,
+            goals,
+            retain_reachable,
+            incremental_search,
+            parallel_search,
+            searched_at,
             start_time,
+            stopped_at,
+            cancellation_token,
             scheduler: _,
+            phases,
+            phase_rounds,
         } = self;
 
         f.debug_struct("Runner")
@@ -197,11 +238,22 @@ where
             .field("roots", roots)
             .field("stop_reason", stop_reason)
             .field("hooks", &vec![format_args!("<dyn FnMut ..>"); hooks.len()])
+            .field("phase_reports", phase_reports)
             .field("iter_limit", iter_limit)
             .field("node_limit", node_limit)
+            .field("memory_limit", memory_limit)
             .field("time_limit", time_limit)
+            .field("goals", goals)
+            .field("retain_reachable", retain_reachable)
+            .field("incremental_search", incremental_search)
+            .field("parallel_search", &parallel_search.is_some())
+            .field("searched_at", searched_at)
             .field("start_time", start_time)
+            .field("stopped_at", stopped_at)
+            .field("cancellation_token", cancellation_token)
             .field("scheduler", &format_args!("<dyn RewriteScheduler ..>"))
+            .field("phases", phases)
+            .field("phase_rounds", phase_rounds)
             .finish()
     }
 }
@@ -210,6 +262,7 @@ where
 ///
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde-1", derive(serde::Serialize))]
+#[non_exhaustive]
 pub enum StopReason {
     /// The egraph saturated, i.e., there was an iteration where we
     /// didn't learn anything new from applying the rules.
@@ -220,10 +273,96 @@ pub enum StopReason {
     NodeLimit(usize),
     /// The time limit was hit. The data is the time limit in seconds.
     TimeLimit(f64),
+    /// The memory limit was hit. The data is the
+    /// [approximate memory usage](EGraph::approx_memory_usage) in bytes.
+    MemoryLimit(usize),
+    /// The [`CancellationToken`] given to the [`Runner`] was cancelled.
+    Cancelled,
+    /// One of the goals given to the [`Runner`] was reached.
+    /// The data is the index of the goal, in the order the goals were
+    /// added with [`with_goal_equivalence`](Runner::with_goal_equivalence())
+    /// and [`with_goal_patterns`](Runner::with_goal_patterns()).
+    GoalReached(usize),
     /// Some other reason to stop.
     Other(String),
 }
 
+/// A condition the [`Runner`] checks after each iteration.
+#[derive(Debug)]
+enum Goal<L: Language> {
+    Equivalence(RecExpr<L>, RecExpr<L>),
+    Patterns(Id, Vec<Pattern<L>>),
+}
+
+impl<L: Language> Goal<L> {
+    fn is_reached<N: Analysis<L>>(&self, egraph: &EGraph<L, N>) -> bool {
+        match self {
+            Goal::Equivalence(lhs, rhs) => match egraph.lookup_expr(lhs) {
+                Some(lhs) => egraph.lookup_expr(rhs) == Some(lhs),
+                None => false,
+            },
+            Goal::Patterns(root, patterns) => {
+                let root = egraph.find(*root);
+                patterns
+                    .iter()
+                    .all(|p| p.search_eclass(egraph, root).is_some())
+            }
+        }
+    }
+}
+
+/// A handle to cancel a [`Runner`], possibly from another thread.
+///
+/// Give a clone of the token to the runner with
+/// [`Runner::with_cancellation_token`], and call
+/// [`cancel`](CancellationToken::cancel()) to make it stop with
+/// [`StopReason::Cancelled`].
+/// The runner checks the token between rules, and
+/// [`Pattern`]s also check it (through [`EGraph::is_cancelled`]) while
+/// searching and applying, so even long iterations stop soon.
+///
+/// # Example
+/// ```
+/// # use egg::*;
+/// // this never saturates
+/// let rules: &[Rewrite<SymbolLang, ()>] = &[rewrite!("grow"; "(f ?a)" => "(f (g ?a))")];
+///
+/// let token = CancellationToken::new();
+/// let cancel = token.clone();
+/// std::thread::spawn(move || {
+///     std::thread::sleep(std::time::Duration::from_millis(10));
+///     cancel.cancel();
+/// });
+///
+/// let runner = Runner::<SymbolLang, ()>::default()
+///     .with_expr(&"(f x)".parse().unwrap())
+///     .with_iter_limit(usize::MAX)
+///     .with_node_limit(usize::MAX)
+///     .with_time_limit(std::time::Duration::from_secs(60))
+///     .with_cancellation_token(token)
+///     .run(rules);
+/// assert!(matches!(runner.stop_reason, Some(StopReason::Cancelled)));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create a new token that isn't cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel every [`Runner`] using this token (or a clone of it).
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed)
+    }
+
+    /// Whether [`cancel`](CancellationToken::cancel()) has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
 /// A report containing data about an entire [`Runner`] run.
 ///
 /// This is basically a summary of the [`Iteration`] data,
@@ -245,6 +384,10 @@ pub struct Report {
     pub search_time: f64,
     pub apply_time: f64,
     pub rebuild_time: f64,
+    /// The [`RuleStats`] of each rule, summed across iterations.
+    pub rule_stats: IndexMap<Symbol, RuleStats>,
+    /// The [`PhaseReport`]s, if the runner ran [`Phase`]s.
+    pub phases: Vec<PhaseReport>,
 }
 
 impl std::fmt::Display for Report {
@@ -260,10 +403,53 @@ impl std::fmt::Display for Report {
         writeln!(f, "    Search:  ({:.2}) {}", self.search_time / self.total_time, self.search_time)?;
         writeln!(f, "    Apply:   ({:.2}) {}", self.apply_time / self.total_time, self.apply_time)?;
         writeln!(f, "    Rebuild: ({:.2}) {}", self.rebuild_time / self.total_time, self.rebuild_time)?;
+        if !self.rule_stats.is_empty() {
+            writeln!(f, "  Rules:")?;
+        }
+        for (name, stats) in &self.rule_stats {
+            writeln!(f, "    {}: search {}, {} matches, {} applied, {} banned",
+                     name, stats.search_time, stats.matches, stats.applied, stats.times_banned)?;
+        }
+        if !self.phases.is_empty() {
+            writeln!(f, "  Phases:")?;
+        }
+        for phase in &self.phases {
+            writeln!(f, "    {} (round {}): {} iterations, {} total time, stop reason: {:?}",
+                     phase.name, phase.round, phase.iterations, phase.total_time, phase.stop_reason)?;
+        }
         Ok(())
     }
 }
 
+/// Statistics about one rule, as collected per [`Iteration`] and summed in
+/// the [`Report`].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct RuleStats {
+    /// Seconds spent searching for this rule.
+    pub search_time: f64,
+    /// The number of matches the [`RewriteScheduler`] returned
+    /// for this rule, i.e. after
+    /// [`filter_matches`](RewriteScheduler::filter_matches()),
+    /// so this is 0 when the rule was banned or its matches were dropped.
+    pub matches: usize,
+    /// The number of applications of this rule that changed the egraph.
+    pub applied: usize,
+    /// The number of times the [`RewriteScheduler`] banned this rule,
+    /// see [`RewriteScheduler::times_banned`].
+    pub times_banned: usize,
+}
+
+impl RuleStats {
+    fn add(&mut self, other: &RuleStats) {
+        self.search_time += other.search_time;
+        self.matches += other.matches;
+        self.applied += other.applied;
+        self.times_banned += other.times_banned;
+    }
+}
+
 /// Data generated by running a [`Runner`] one iteration.
 ///
 /// If the `serde-1` feature is enabled, this implements
@@ -284,6 +470,10 @@ pub struct Iteration<IterData> {
     /// A map from rule name to number of times it was _newly_ applied
     /// in this iteration.
     pub applied: IndexMap<Symbol, usize>,
+    /// A map from rule name to [`RuleStats`] for this iteration,
+    /// with an entry for every rule the runner was given, even if the
+    /// iteration stopped before it was searched.
+    pub rule_stats: IndexMap<Symbol, RuleStats>,
     /// Seconds spent running hooks.
     pub hook_time: f64,
     /// Seconds spent searching in this iteration.
@@ -301,10 +491,228 @@ pub struct Iteration<IterData> {
     pub n_rebuilds: usize,
     /// If the runner stopped on this iterations, this is the reason
     pub stop_reason: Option<StopReason>,
+    /// The name of the [`Phase`] this iteration was part of, if any.
+    pub phase: Option<Symbol>,
+}
+
+/** A named group of [`Rewrite`]s that a [`Runner`] runs as one phase.
+
+Phases are added with [`Runner::with_phase`] and run in order by
+[`Runner::run_phases`], all on the same [`EGraph`].
+Each phase runs until its rules saturate or one of its own limits is hit,
+and then the next phase starts.
+A phase can also [loop back](Phase::with_loop_back()) to an earlier phase,
+re-running the phases in between until they stop applying rewrites, and
+the whole sequence can be run in several
+[rounds](Runner::with_phase_rounds()).
+The [`Runner`]'s limits still apply to the whole run.
+
+A phase uses the [`Runner`]'s [`RewriteScheduler`] unless it is given its
+own with [`with_scheduler`](Phase::with_scheduler()).
+A phase's scheduler is kept across
+[rounds](Runner::with_phase_rounds()).
+
+`Phase` is configurable in the builder-pattern style.
+
+# Example
+```
+use egg::{*, rewrite as rw};
+
+let expand: &[Rewrite<SymbolLang, ()>] = &[
+    rw!("distribute"; "(* ?a (+ ?b ?c))" => "(+ (* ?a ?b) (* ?a ?c))"),
+];
+let simplify: &[Rewrite<SymbolLang, ()>] = &[
+    rw!("mul-1"; "(* ?a 1)" => "?a"),
+    rw!("add-0"; "(+ ?a 0)" => "?a"),
+];
+
+let runner = Runner::default()
+    .with_expr(&"(* x (+ 1 0))".parse().unwrap())
+    .with_phase(Phase::new("expand", expand).with_iter_limit(5))
+    .with_phase(Phase::new("simplify", simplify).with_scheduler(SimpleScheduler))
+    .run_phases();
+
+let names: Vec<_> = runner.phase_reports.iter().map(|p| p.name.as_str()).collect();
+assert_eq!(names, ["expand", "simplify"]);
+assert!(matches!(runner.stop_reason, Some(StopReason::Saturated)));
+assert_eq!(
+    runner.egraph.find(runner.roots[0]),
+    runner.egraph.lookup_expr(&"x".parse().unwrap()).unwrap(),
+);
+```
+*/
+pub struct Phase<L: Language, N: Analysis<L>> {
+    name: Symbol,
+    rules: Vec<Rewrite<L, N>>,
+    iter_limit: usize,
+    node_limit: usize,
+    time_limit: Duration,
+    scheduler: Option<Box<dyn RewriteScheduler<L, N>>>,
+    loop_back: Option<(Symbol, usize)>,
+}
+
+impl<L: Language, N: Analysis<L>> Phase<L, N> {
+    /// Create a new `Phase` with the given name and rules, and no limits
+    /// of its own.
+    pub fn new<'a, R>(name: impl Into<Symbol>, rules: R) -> Self
+    where
+        R: IntoIterator<Item = &'a Rewrite<L, N>>,
+        L: 'a,
+        N: 'a,
+    {
+        Self {
+            name: name.into(),
+            rules: rules.into_iter().cloned().collect(),
+            iter_limit: usize::MAX,
+            node_limit: usize::MAX,
+            time_limit: Duration::MAX,
+            scheduler: None,
+            loop_back: None,
+        }
+    }
+
+    /// Returns the name of this phase.
+    pub fn name(&self) -> Symbol {
+        self.name
+    }
+
+    /// Returns the rules of this phase.
+    pub fn rules(&self) -> &[Rewrite<L, N>] {
+        &self.rules
+    }
+
+    /// Sets the number of iterations this phase may run each time it runs.
+    /// Default: no limit
+    pub fn with_iter_limit(self, iter_limit: usize) -> Self {
+        Self { iter_limit, ..self }
+    }
+
+    /// Ends this phase when the egraph is bigger than this (in enodes).
+    /// Default: no limit
+    pub fn with_node_limit(self, node_limit: usize) -> Self {
+        Self { node_limit, ..self }
+    }
+
+    /// Sets how long this phase may run each time it runs.
+    /// Default: no limit
+    pub fn with_time_limit(self, time_limit: Duration) -> Self {
+        Self { time_limit, ..self }
+    }
+
+    /// Use the given [`RewriteScheduler`] for this phase instead of the
+    /// [`Runner`]'s.
+    pub fn with_scheduler(self, scheduler: impl RewriteScheduler<L, N> + 'static) -> Self {
+        let scheduler: Option<Box<dyn RewriteScheduler<L, N>>> = Some(Box::new(scheduler));
+        Self { scheduler, ..self }
+    }
+
+    /// After this phase ends, go back to the phase named `to` (this one or
+    /// an earlier one) and run the phases from there up to this one again,
+    /// as long as they applied some rewrite, but at most `max_loops` times
+    /// per [round](Runner::with_phase_rounds()).
+    /// Default: no loop
+    ///
+    /// [`Runner::run_phases`] panics if there is no such phase.
+    pub fn with_loop_back(self, to: impl Into<Symbol>, max_loops: usize) -> Self {
+        Self {
+            loop_back: Some((to.into(), max_loops)),
+            ..self
+        }
+    }
+}
+
+impl<L: Language, N: Analysis<L>> Debug for Phase<L, N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // Use an exhaustive pattern match to ensure the Debug implementation and the struct stay in sync.
+        let Phase {
+            name,
+            rules,
+            iter_limit,
+            node_limit,
+            time_limit,
+            scheduler,
+            loop_back,
+        } = self;
+
+        f.debug_struct("Phase")
+            .field("name", name)
+            .field("rules", &rules.iter().map(|r| r.name).collect::<Vec<_>>())
+            .field("iter_limit", iter_limit)
+            .field("node_limit", node_limit)
+            .field("time_limit", time_limit)
+            .field(
+                "scheduler",
+                &scheduler
+                    .as_ref()
+                    .map(|_| format_args!("<dyn RewriteScheduler ..>")),
+            )
+            .field("loop_back", loop_back)
+            .finish()
+    }
+}
+
+/// Data about one run of a [`Phase`] by [`Runner::run_phases`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct PhaseReport {
+    /// The name of the [`Phase`].
+    pub name: Symbol,
+    /// The round this phase ran in, starting from 0,
+    /// see [`Runner::with_phase_rounds`].
+    pub round: usize,
+    /// The index of the first [`Iteration`] of this phase in
+    /// [`Runner::iterations`].
+    pub first_iteration: usize,
+    /// The number of iterations this phase ran.
+    pub iterations: usize,
+    /// Seconds spent in the iterations of this phase.
+    pub total_time: f64,
+    /// Why this phase ended.
+    pub stop_reason: StopReason,
 }
 
 type RunnerResult<T> = std::result::Result<T, StopReason>;
 
+/// Searches each of the given rewrites, with [`Rewrite::search_since`] if a
+/// generation is given, returning the matches and the seconds spent searching
+/// in the same order.
+type ParallelSearchFn<L, N> = for<'a> fn(
+    &EGraph<L, N>,
+    &[(&'a Rewrite<L, N>, Option<usize>)],
+) -> Vec<(Vec<SearchMatches<'a, L>>, f64)>;
+
+#[cfg(feature = "parallel")]
+fn search_in_parallel<'a, L, N>(
+    egraph: &EGraph<L, N>,
+    rewrites: &[(&'a Rewrite<L, N>, Option<usize>)],
+) -> Vec<(Vec<SearchMatches<'a, L>>, f64)>
+where
+    L: Language + Send + Sync,
+    N: Analysis<L> + Sync,
+    N::Data: Sync,
+{
+    use rayon::prelude::*;
+    rewrites
+        .par_iter()
+        .map(|&(rewrite, since)| {
+            let start_time = Instant::now();
+            let searcher = &rewrite.searcher;
+            let matches = match (searcher.eclasses_to_search(egraph, since), since) {
+                // rayon's collect into a Vec keeps the order of the e-classes
+                (Some(eclasses), _) => eclasses
+                    .into_par_iter()
+                    .filter(|_| !egraph.is_cancelled())
+                    .filter_map(|id| searcher.search_eclass(egraph, id))
+                    .collect(),
+                (None, Some(since)) => rewrite.search_since(egraph, since),
+                (None, None) => rewrite.search(egraph),
+            };
+            (matches, start_time.elapsed().as_secs_f64())
+        })
+        .collect()
+}
+
 impl<L, N, IterData> Runner<L, N, IterData>
 where
     L: Language,
@@ -316,16 +724,27 @@ where
         Self {
             iter_limit: 30,
             node_limit: 10_000,
+            memory_limit: usize::MAX,
             time_limit: Duration::from_secs(5),
 
+            goals: vec![],
+            retain_reachable: false,
+            incremental_search: false,
+            parallel_search: None,
+            searched_at: Default::default(),
             egraph: EGraph::new(analysis),
             roots: vec![],
             iterations: vec![],
             stop_reason: None,
             hooks: vec![],
+            phase_reports: vec![],
 
             start_time: None,
+            stopped_at: None,
+            cancellation_token: None,
             scheduler: Box::new(BackoffScheduler::default()),
+            phases: vec![],
+            phase_rounds: 1,
         }
     }
 
@@ -339,11 +758,126 @@ where
         Self { node_limit, ..self }
     }
 
+    /// Sets the limit on the egraph's
+    /// [approximate memory usage](EGraph::approx_memory_usage), in bytes.
+    /// Default: no limit
+    ///
+    /// Checking this limit takes time linear in the size of the egraph,
+    /// so it is only checked once per iteration, after rebuilding.
+    /// The egraph may go over the limit while rules are applied.
+    pub fn with_memory_limit(self, memory_limit: usize) -> Self {
+        Self {
+            memory_limit,
+            ..self
+        }
+    }
+
     /// Sets the runner time limit. Default: 5 seconds
     pub fn with_time_limit(self, time_limit: Duration) -> Self {
         Self { time_limit, ..self }
     }
 
+    /// Stop with [`StopReason::GoalReached`] once the two expressions are
+    /// equivalent in the egraph.
+    ///
+    /// This is checked after each iteration with
+    /// [`lookup_expr`](EGraph::lookup_expr()), so both expressions have to
+    /// be represented in the egraph; add them with
+    /// [`with_expr`](Runner::with_expr()) if the rules won't create them.
+    ///
+    /// # Example
+    /// ```
+    /// # use egg::*;
+    /// let rules: &[Rewrite<SymbolLang, ()>] = &[
+    ///     rewrite!("commute-add"; "(+ ?a ?b)" => "(+ ?b ?a)"),
+    ///     rewrite!("assoc-add"; "(+ ?a (+ ?b ?c))" => "(+ (+ ?a ?b) ?c)"),
+    /// ];
+    ///
+    /// let lhs = "(+ a (+ b (+ c d)))".parse().unwrap();
+    /// let rhs = "(+ (+ d c) (+ b a))".parse().unwrap();
+    /// let runner = Runner::<SymbolLang, ()>::default()
+    ///     .with_expr(&lhs)
+    ///     .with_expr(&rhs)
+    ///     .with_goal_equivalence(&lhs, &rhs)
+    ///     .run(rules);
+    /// assert!(matches!(runner.stop_reason, Some(StopReason::GoalReached(0))));
+    /// assert_eq!(runner.egraph.find(runner.roots[0]), runner.egraph.find(runner.roots[1]));
+    /// ```
+    pub fn with_goal_equivalence(mut self, lhs: &RecExpr<L>, rhs: &RecExpr<L>) -> Self {
+        self.goals.push(Goal::Equivalence(lhs.clone(), rhs.clone()));
+        self
+    }
+
+    /// Stop with [`StopReason::GoalReached`] once all the patterns match
+    /// in the eclass of `root`, which is checked after each iteration with
+    /// [`Searcher::search_eclass`].
+    ///
+    /// # Example
+    /// ```
+    /// # use egg::*;
+    /// let rules: &[Rewrite<SymbolLang, ()>] = &[
+    ///     rewrite!("mul-2"; "(* ?a 2)" => "(+ ?a ?a)"),
+    ///     rewrite!("shift"; "(* ?a 2)" => "(<< ?a 1)"),
+    /// ];
+    ///
+    /// let runner = Runner::<SymbolLang, ()>::default()
+    ///     .with_expr(&"(* x 2)".parse().unwrap());
+    /// let root = runner.roots[0];
+    /// let runner = runner
+    ///     .with_goal_patterns(root, &["(<< ?a 1)".parse().unwrap()])
+    ///     .run(rules);
+    /// assert!(matches!(runner.stop_reason, Some(StopReason::GoalReached(0))));
+    /// ```
+    pub fn with_goal_patterns(mut self, root: Id, patterns: &[Pattern<L>]) -> Self {
+        self.goals.push(Goal::Patterns(root, patterns.to_vec()));
+        self
+    }
+
+    /// Garbage collect the e-graph after each iteration, deleting every
+    /// e-class that is not reachable from the [`roots`](Runner::roots)
+    /// with [`EGraph::retain_reachable`]. Default: false
+    ///
+    /// The [`roots`](Runner::roots) are updated to the new [`Id`]s, but any
+    /// other [`Id`]s you hold on to (e.g. in hooks) will be invalidated.
+    /// This can't be used together with explanations.
+    pub fn with_retain_reachable(self, retain_reachable: bool) -> Self {
+        Self {
+            retain_reachable,
+            ..self
+        }
+    }
+
+    /// Only search the parts of the e-graph that changed since each rule
+    /// was last searched, instead of the whole e-graph. Default: false
+    ///
+    /// This uses [`RewriteScheduler::search_rewrite_since`] and
+    /// [`Searcher::search_since`], so matches that were already found
+    /// in an earlier iteration are mostly not found again.
+    /// Note that this changes how many matches a rule has in each
+    /// iteration, which affects schedulers like [`BackoffScheduler`].
+    ///
+    /// Only changes made through the [`EGraph`] are tracked, and only the
+    /// e-classes a rule's pattern matched (including the ones bound to its
+    /// variables) are watched for changes, so a [`Condition`] that depends
+    /// on other e-classes or outside state may miss matches.
+    pub fn with_incremental_search(self, incremental_search: bool) -> Self {
+        Self {
+            incremental_search,
+            ..self
+        }
+    }
+
+    /// Stop with [`StopReason::Cancelled`] once the given token is
+    /// [cancelled](CancellationToken::cancel()).
+    ///
+    /// To resume a cancelled `Runner`, give it a new token.
+    pub fn with_cancellation_token(self, cancellation_token: CancellationToken) -> Self {
+        Self {
+            cancellation_token: Some(cancellation_token),
+            ..self
+        }
+    }
+
     /// Add a hook to instrument or modify the behavior of a [`Runner`].
     /// Each hook will run at the beginning of each iteration, i.e. before
     /// all the rewrites.
@@ -383,6 +917,28 @@ where
         Self { scheduler, ..self }
     }
 
+    /// Add a [`Phase`] to be run by [`run_phases`](Runner::run_phases()),
+    /// after the phases added before it.
+    pub fn with_phase(mut self, phase: Phase<L, N>) -> Self {
+        self.phases.push(phase);
+        self
+    }
+
+    /// Run all the phases again and again, until a round of them
+    /// doesn't apply any rewrite, but at most `phase_rounds` times.
+    /// Default: 1, i.e. the phases run only once.
+    ///
+    /// To re-run only some of the phases, see [`Phase::with_loop_back`].
+    ///
+    /// Panics if `phase_rounds` is 0.
+    pub fn with_phase_rounds(self, phase_rounds: usize) -> Self {
+        assert!(phase_rounds > 0, "phase_rounds must be at least 1");
+        Self {
+            phase_rounds,
+            ..self
+        }
+    }
+
     /// Add an expression to the egraph to be run.
     ///
     /// The eclass id of this addition will be recorded in the
@@ -396,13 +952,46 @@ where
 
     /// Replace the [`EGraph`] of this `Runner`.
     pub fn with_egraph(self, egraph: EGraph<L, N>) -> Self {
-        Self { egraph, ..self }
+        Self {
+            egraph,
+            searched_at: Default::default(),
+            ..self
+        }
     }
 
     /// Run this `Runner` until it stops.
     /// After this, the field
     /// [`stop_reason`](Runner::stop_reason) is guaranteed to be
     /// set.
+    ///
+    /// A stopped `Runner` can be run again, e.g. with higher limits or
+    /// other rules, and it will pick up where it left off, keeping its
+    /// [`iterations`](Runner::iterations) and the state of its
+    /// [`RewriteScheduler`].
+    /// The time the `Runner` spent stopped doesn't count towards the time
+    /// limit.
+    /// If it is still over one of its limits, it stops again right away
+    /// without doing another iteration.
+    ///
+    /// # Example
+    /// ```
+    /// # use egg::*;
+    /// let rules: &[Rewrite<SymbolLang, ()>] = &[
+    ///     rewrite!("commute-add"; "(+ ?a ?b)" => "(+ ?b ?a)"),
+    ///     rewrite!("assoc-add"; "(+ ?a (+ ?b ?c))" => "(+ (+ ?a ?b) ?c)"),
+    /// ];
+    ///
+    /// let runner = Runner::<SymbolLang, ()>::default()
+    ///     .with_expr(&"(+ a (+ b (+ c d)))".parse().unwrap())
+    ///     .with_iter_limit(2)
+    ///     .run(rules);
+    /// assert!(matches!(runner.stop_reason, Some(StopReason::IterationLimit(2))));
+    ///
+    /// // keep going with a bigger budget
+    /// let runner = runner.with_iter_limit(100).run(rules);
+    /// assert!(matches!(runner.stop_reason, Some(StopReason::Saturated)));
+    /// assert!(runner.iterations.len() > 2);
+    /// ```
     pub fn run<'a, R>(mut self, rules: R) -> Self
     where
         R: IntoIterator<Item = &'a Rewrite<L, N>>,
@@ -411,15 +1000,14 @@ where
     {
         let rules: Vec<&Rewrite<L, N>> = rules.into_iter().collect();
         check_rules(&rules);
+        if let Err(stop_reason) = self.resume() {
+            self.stop(stop_reason);
+            return self;
+        }
         self.egraph.rebuild();
         loop {
-            let iter = self.run_one(&rules);
-            self.iterations.push(iter);
-            let stop_reason = self.iterations.last().unwrap().stop_reason.clone();
-            // we need to check_limits after the iteration is complete to check for iter_limit
-            if let Some(stop_reason) = stop_reason.or_else(|| self.check_limits().err()) {
-                info!("Stopping: {:?}", stop_reason);
-                self.stop_reason = Some(stop_reason);
+            if let Some(stop_reason) = self.iterate(&rules) {
+                self.stop(stop_reason);
                 break;
             }
         }
@@ -429,6 +1017,201 @@ where
         self
     }
 
+    /// Run a single iteration of this `Runner` with the given rules,
+    /// returning the [`StopReason`] if it stopped.
+    ///
+    /// This is useful for drivers that want to look at (or modify) the
+    /// runner between iterations.
+    /// Like [`run`](Runner::run()), this resumes a stopped `Runner`,
+    /// and stops right away if the `Runner` is still over one of its
+    /// limits.
+    ///
+    /// # Example
+    /// ```
+    /// # use egg::*;
+    /// let rules: &[Rewrite<SymbolLang, ()>] = &[
+    ///     rewrite!("commute-add"; "(+ ?a ?b)" => "(+ ?b ?a)"),
+    /// ];
+    ///
+    /// let mut runner = Runner::<SymbolLang, ()>::default()
+    ///     .with_expr(&"(+ a b)".parse().unwrap());
+    /// while runner.step(rules).is_none() {
+    ///     println!("Egraph is this big: {}", runner.egraph.total_size());
+    /// }
+    /// assert!(matches!(runner.stop_reason, Some(StopReason::Saturated)));
+    /// assert_eq!(runner.iterations.len(), 2);
+    /// ```
+    pub fn step<'a, R>(&mut self, rules: R) -> Option<StopReason>
+    where
+        R: IntoIterator<Item = &'a Rewrite<L, N>>,
+        L: 'a,
+        N: 'a,
+    {
+        let rules: Vec<&Rewrite<L, N>> = rules.into_iter().collect();
+        check_rules(&rules);
+        if let Err(stop_reason) = self.resume() {
+            self.stop(stop_reason.clone());
+            return Some(stop_reason);
+        }
+        if !self.egraph.clean {
+            self.egraph.rebuild();
+        }
+        let stop_reason = self.iterate(&rules);
+        if let Some(stop_reason) = &stop_reason {
+            self.stop(stop_reason.clone());
+        }
+        stop_reason
+    }
+
+    /// Run the [`Phase`]s added with [`with_phase`](Runner::with_phase())
+    /// in order, in as many [rounds](Runner::with_phase_rounds()) as needed.
+    /// After this, the field
+    /// [`stop_reason`](Runner::stop_reason) is guaranteed to be
+    /// set.
+    ///
+    /// The [`Iteration`]s of all phases are kept together in
+    /// [`iterations`](Runner::iterations), and each run of a phase is
+    /// summarized in [`phase_reports`](Runner::phase_reports).
+    ///
+    /// The runner stops early if one of its limits is hit or a hook fails.
+    /// Otherwise, its stop reason is [`StopReason::Saturated`] if the last
+    /// round didn't apply any rewrite, or the stop reason of the last phase
+    /// that ran if it ran out of rounds.
+    ///
+    /// Like [`run`](Runner::run()), this resumes a stopped `Runner`,
+    /// running all the phases again.
+    pub fn run_phases(mut self) -> Self {
+        assert!(!self.phases.is_empty(), "No phases to run");
+        for phase in &self.phases {
+            check_rules(&phase.rules.iter().collect::<Vec<_>>());
+        }
+        // for each phase, the index of the phase it loops back to
+        let loop_backs: Vec<Option<(usize, usize)>> = self
+            .phases
+            .iter()
+            .enumerate()
+            .map(|(i, phase)| {
+                let (to, max_loops) = phase.loop_back?;
+                let target = self.phases[..=i]
+                    .iter()
+                    .position(|p| p.name == to)
+                    .unwrap_or_else(|| {
+                        panic!(
+                            "Phase {} loops back to {}, which is not an earlier phase",
+                            phase.name, to
+                        )
+                    });
+                Some((target, max_loops))
+            })
+            .collect();
+        if let Err(stop_reason) = self.resume() {
+            self.stop(stop_reason);
+            return self;
+        }
+        let mut phases = std::mem::take(&mut self.phases);
+        self.egraph.rebuild();
+
+        let mut stop_reason = None;
+        'rounds: for round in 0..self.phase_rounds {
+            let round_start = self.iterations.len();
+            let mut phase_starts = vec![round_start; phases.len()];
+            let mut loops = vec![0; phases.len()];
+            let mut i = 0;
+            while i < phases.len() {
+                phase_starts[i] = self.iterations.len();
+                if let Err(reason) = self.run_phase(&mut phases[i], round) {
+                    stop_reason = Some(reason);
+                    break 'rounds;
+                }
+                match loop_backs[i] {
+                    Some((target, max_loops))
+                        if loops[i] < max_loops
+                            && self.iterations[phase_starts[target]..]
+                                .iter()
+                                .any(|iteration| !iteration.applied.is_empty()) =>
+                    {
+                        loops[i] += 1;
+                        // inner loops start over
+                        loops[target..i].iter_mut().for_each(|l| *l = 0);
+                        i = target;
+                    }
+                    _ => i += 1,
+                }
+            }
+            if self.iterations[round_start..]
+                .iter()
+                .all(|i| i.applied.is_empty())
+            {
+                stop_reason = Some(StopReason::Saturated);
+                break;
+            }
+        }
+
+        let stop_reason = stop_reason.unwrap_or_else(|| {
+            let last_phase = self.phase_reports.last().unwrap();
+            last_phase.stop_reason.clone()
+        });
+        self.stop(stop_reason);
+        self.phases = phases;
+
+        assert!(!self.iterations.is_empty());
+        self
+    }
+
+    /// Runs one phase until it ends, recording a [`PhaseReport`].
+    /// Returns an error if the whole run should stop.
+    fn run_phase(&mut self, phase: &mut Phase<L, N>, round: usize) -> RunnerResult<()> {
+        info!("\nPhase {} (round {})", phase.name, round);
+        let rules: Vec<&Rewrite<L, N>> = phase.rules.iter().collect();
+        if let Some(scheduler) = &mut phase.scheduler {
+            std::mem::swap(&mut self.scheduler, scheduler);
+        }
+
+        let first_iteration = self.iterations.len();
+        let start_time = Instant::now();
+        let result = loop {
+            let stop_reason = self.iterate(&rules);
+            self.iterations.last_mut().unwrap().phase = Some(phase.name);
+            match stop_reason {
+                Some(StopReason::Saturated) => break Ok(StopReason::Saturated),
+                Some(stop_reason) => break Err(stop_reason),
+                None => (),
+            }
+
+            let iterations = self.iterations.len() - first_iteration;
+            if iterations >= phase.iter_limit {
+                break Ok(StopReason::IterationLimit(iterations));
+            }
+            let size = self.egraph.total_size();
+            if size > phase.node_limit {
+                break Ok(StopReason::NodeLimit(size));
+            }
+            let elapsed = start_time.elapsed();
+            if elapsed > phase.time_limit {
+                break Ok(StopReason::TimeLimit(elapsed.as_secs_f64()));
+            }
+        };
+
+        if let Some(scheduler) = &mut phase.scheduler {
+            std::mem::swap(&mut self.scheduler, scheduler);
+        }
+
+        let stop_reason = match &result {
+            Ok(stop_reason) | Err(stop_reason) => stop_reason.clone(),
+        };
+        info!("Phase {} ended: {:?}", phase.name, stop_reason);
+        let iterations = &self.iterations[first_iteration..];
+        self.phase_reports.push(PhaseReport {
+            name: phase.name,
+            round,
+            first_iteration,
+            iterations: iterations.len(),
+            total_time: iterations.iter().map(|i| i.total_time).sum(),
+            stop_reason,
+        });
+        result.map(|_| ())
+    }
+
     /// Enable explanations for this runner's egraph.
     /// This allows the runner to explain why two expressions are
     /// equivalent with the [`explain_equivalence`](Runner::explain_equivalence) function.
@@ -443,6 +1226,18 @@ where
         self
     }
 
+    /// Calls [`EGraph::with_explanation_length_optimization`](EGraph::with_explanation_length_optimization()).
+    pub fn with_explanation_length_optimization(mut self) -> Self {
+        self.egraph = self.egraph.with_explanation_length_optimization();
+        self
+    }
+
+    /// Calls [`EGraph::without_explanation_length_optimization`](EGraph::without_explanation_length_optimization()).
+    pub fn without_explanation_length_optimization(mut self) -> Self {
+        self.egraph = self.egraph.without_explanation_length_optimization();
+        self
+    }
+
     /// Calls [`EGraph::explain_equivalence`](EGraph::explain_equivalence()).
     pub fn explain_equivalence(&mut self, left: &RecExpr<L>, right: &RecExpr<L>) -> Explanation<L> {
         self.egraph.explain_equivalence(left, right)
@@ -490,6 +1285,16 @@ where
             apply_time: self.iterations.iter().map(|i| i.apply_time).sum(),
             rebuild_time: self.iterations.iter().map(|i| i.rebuild_time).sum(),
             total_time: self.iterations.iter().map(|i| i.total_time).sum(),
+            rule_stats: {
+                let mut rule_stats = IndexMap::<Symbol, RuleStats>::default();
+                for iteration in &self.iterations {
+                    for (name, stats) in &iteration.rule_stats {
+                        rule_stats.entry(*name).or_default().add(stats);
+                    }
+                }
+                rule_stats
+            },
+            phases: self.phase_reports.clone(),
         }
     }
 
@@ -500,6 +1305,7 @@ where
 
         self.try_start();
         let mut result = self.check_limits();
+        self.egraph.cancellation_token = self.cancellation_token.clone();
 
         let egraph_nodes = self.egraph.total_size();
         let egraph_classes = self.egraph.number_of_classes();
@@ -523,12 +1329,47 @@ where
         let start_time = Instant::now();
 
         let mut matches = Vec::new();
-        result = result.and_then(|_| {
-            rules.iter().try_for_each(|rule| {
-                let ms = self.scheduler.search_rewrite(i, &self.egraph, rule);
+        let mut searched = Vec::new();
+        let mut rule_stats: IndexMap<Symbol, RuleStats> = rules
+            .iter()
+            .map(|rw| (rw.name, RuleStats::default()))
+            .collect();
+        let generation = if self.incremental_search {
+            self.egraph.new_generation()
+        } else {
+            self.egraph.generation()
+        };
+        result = result.and_then(|_| match self.parallel_search {
+            Some(search) if self.scheduler.can_search_in_parallel() => self.search_in_parallel(
+                search,
+                i,
+                rules,
+                &mut matches,
+                &mut searched,
+                &mut rule_stats,
+            ),
+            _ => rules.iter().try_for_each(|rule| {
+                let banned_before = self.scheduler.times_banned(rule);
+                let rule_start_time = Instant::now();
+                let ms = if self.incremental_search {
+                    let since = self.searched_at.get(&rule.name).copied().unwrap_or(0);
+                    let ms = self
+                        .scheduler
+                        .search_rewrite_since(i, &self.egraph, rule, since);
+                    searched.push(ms.is_some());
+                    ms.unwrap_or_default()
+                } else {
+                    self.scheduler.search_rewrite(i, &self.egraph, rule)
+                };
+                let stats = rule_stats.entry(rule.name).or_default();
+                stats.search_time += rule_start_time.elapsed().as_secs_f64();
+                stats.times_banned += self
+                    .scheduler
+                    .times_banned(rule)
+                    .saturating_sub(banned_before);
                 matches.push(ms);
                 self.check_limits()
-            })
+            }),
         });
 
         let search_time = start_time.elapsed().as_secs_f64();
@@ -538,25 +1379,49 @@ where
 
         let mut applied = IndexMap::default();
         result = result.and_then(|_| {
-            rules.iter().zip(matches).try_for_each(|(rw, ms)| {
-                let total_matches: usize = ms.iter().map(|m| m.substs.len()).sum();
-                debug!("Applying {} {} times", rw.name, total_matches);
-
-                let actually_matched = self.scheduler.apply_rewrite(i, &mut self.egraph, rw, ms);
-                if actually_matched > 0 {
-                    if let Some(count) = applied.get_mut(&rw.name) {
-                        *count += actually_matched;
-                    } else {
-                        applied.insert(rw.name.to_owned(), actually_matched);
+            rules
+                .iter()
+                .zip(matches)
+                .enumerate()
+                .try_for_each(|(i_rule, (rw, ms))| {
+                    let total_matches: usize = ms.iter().map(|m| m.substs.len()).sum();
+                    debug!("Applying {} {} times", rw.name, total_matches);
+
+                    let actually_matched =
+                        self.scheduler.apply_rewrite(i, &mut self.egraph, rw, ms);
+                    let stats = rule_stats.entry(rw.name).or_default();
+                    stats.matches += total_matches;
+                    stats.applied += actually_matched;
+                    // a cancelled search or apply may have skipped matches,
+                    // so they have to be found again next time
+                    if searched.get(i_rule) == Some(&true) && !self.egraph.is_cancelled() {
+                        self.searched_at.insert(rw.name, generation);
                     }
-                    debug!("Applied {} {} times", rw.name, actually_matched);
-                }
-                self.check_limits()
-            })
+                    if actually_matched > 0 {
+                        if let Some(count) = applied.get_mut(&rw.name) {
+                            *count += actually_matched;
+                        } else {
+                            applied.insert(rw.name.to_owned(), actually_matched);
+                        }
+                        debug!("Applied {} {} times", rw.name, actually_matched);
+                    }
+                    self.check_limits()
+                })
         });
 
         let apply_time = apply_time.elapsed().as_secs_f64();
         info!("Apply time: {}", apply_time);
+        self.egraph.cancellation_token = None;
+
+        if self.incremental_search {
+            // rules that weren't searched yet do a full search anyway
+            let oldest = rules
+                .iter()
+                .filter_map(|rw| self.searched_at.get(&rw.name).copied())
+                .min()
+                .unwrap_or_else(|| self.egraph.generation());
+            self.egraph.forget_generations_before(oldest);
+        }
 
         let rebuild_time = Instant::now();
         let n_rebuilds = self.egraph.rebuild();
@@ -564,6 +1429,31 @@ where
             debug_assert!(self.egraph.check_each_explain(rules));
         }
 
+        if self.retain_reachable {
+            let mut roots = self.roots.clone();
+            for goal in &self.goals {
+                if let Goal::Patterns(root, _) = goal {
+                    roots.push(*root);
+                }
+            }
+            let remap = self.egraph.retain_reachable(&roots);
+            for root in &mut self.roots {
+                *root = remap[root];
+            }
+            for goal in &mut self.goals {
+                if let Goal::Patterns(root, _) = goal {
+                    *root = remap[root];
+                }
+            }
+        }
+
+        if let Some(i) = self.goals.iter().position(|g| g.is_reached(&self.egraph)) {
+            info!("Reached goal {}", i);
+            result = result.and(Err(StopReason::GoalReached(i)));
+        }
+
+        result = result.and_then(|_| self.check_memory_limit());
+
         let rebuild_time = rebuild_time.elapsed().as_secs_f64();
         info!("Rebuild time: {}", rebuild_time);
         info!(
@@ -583,6 +1473,7 @@ where
 
         Iteration {
             applied,
+            rule_stats,
             egraph_nodes,
             egraph_classes,
             hook_time,
@@ -593,7 +1484,84 @@ where
             data: IterData::make(self),
             total_time: start_time.elapsed().as_secs_f64(),
             stop_reason: result.err(),
+            phase: None,
+        }
+    }
+
+    /// Searches the rewrites with the given parallel search function,
+    /// asking the scheduler which rewrites to search and which matches
+    /// to keep in the same order as the sequential search.
+    fn search_in_parallel<'a>(
+        &mut self,
+        search: ParallelSearchFn<L, N>,
+        iteration: usize,
+        rules: &[&'a Rewrite<L, N>],
+        matches: &mut Vec<Vec<SearchMatches<'a, L>>>,
+        searched: &mut Vec<bool>,
+        rule_stats: &mut IndexMap<Symbol, RuleStats>,
+    ) -> RunnerResult<()> {
+        let mut should_search = vec![];
+        let mut to_search = vec![];
+        let mut banned_before = vec![];
+        for &rule in rules {
+            banned_before.push(self.scheduler.times_banned(rule));
+            let should = self.scheduler.should_search_rewrite(iteration, rule);
+            if should {
+                let since = self.searched_at.get(&rule.name).copied().unwrap_or(0);
+                to_search.push((rule, self.incremental_search.then(|| since)));
+            }
+            should_search.push(should);
+        }
+
+        let mut found = search(&self.egraph, &to_search).into_iter();
+        for ((&rule, should), banned_before) in rules.iter().zip(should_search).zip(banned_before) {
+            let stats = rule_stats.entry(rule.name).or_default();
+            let ms = if should {
+                let (ms, search_time) = found.next().unwrap();
+                stats.search_time += search_time;
+                self.scheduler.filter_matches(iteration, rule, ms)
+            } else {
+                None
+            };
+            stats.times_banned += self
+                .scheduler
+                .times_banned(rule)
+                .saturating_sub(banned_before);
+            searched.push(ms.is_some());
+            matches.push(ms.unwrap_or_default());
+        }
+        self.check_limits()
+    }
+
+    /// Runs one iteration, returning why the runner should stop, if it should.
+    fn iterate(&mut self, rules: &[&Rewrite<L, N>]) -> Option<StopReason> {
+        let iter = self.run_one(rules);
+        self.iterations.push(iter);
+        let stop_reason = self.iterations.last().unwrap().stop_reason.clone();
+        // we need to check_limits after the iteration is complete to check for iter_limit
+        stop_reason.or_else(|| self.check_limits().err())
+    }
+
+    fn stop(&mut self, stop_reason: StopReason) {
+        info!("Stopping: {:?}", stop_reason);
+        self.stop_reason = Some(stop_reason);
+        self.stopped_at = Some(Instant::now());
+    }
+
+    /// Clears the stop reason of a stopped runner, returning an error if
+    /// it is still over one of its limits.
+    fn resume(&mut self) -> RunnerResult<()> {
+        if self.stop_reason.take().is_none() {
+            return Ok(());
+        }
+        info!("Resuming");
+        if let (Some(start_time), Some(stopped_at)) = (&mut self.start_time, self.stopped_at) {
+            *start_time += stopped_at.elapsed();
         }
+        self.stopped_at = None;
+        self.try_start();
+        self.check_limits()?;
+        self.check_memory_limit()
     }
 
     fn try_start(&mut self) {
@@ -601,6 +1569,12 @@ where
     }
 
     fn check_limits(&self) -> RunnerResult<()> {
+        if let Some(token) = &self.cancellation_token {
+            if token.is_cancelled() {
+                return Err(StopReason::Cancelled);
+            }
+        }
+
         let elapsed = self.start_time.unwrap().elapsed();
         if elapsed > self.time_limit {
             return Err(StopReason::TimeLimit(elapsed.as_secs_f64()));
@@ -617,6 +1591,56 @@ where
 
         Ok(())
     }
+
+    /// Checks the memory limit, which takes time linear in the size of the
+    /// egraph, unlike the other limits in [`check_limits`](Runner::check_limits).
+    fn check_memory_limit(&self) -> RunnerResult<()> {
+        if self.memory_limit != usize::MAX {
+            let memory = self.egraph.approx_memory_usage().total();
+            if memory > self.memory_limit {
+                return Err(StopReason::MemoryLimit(memory));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<L, N, IterData> Runner<L, N, IterData>
+where
+    L: Language + Send + Sync,
+    N: Analysis<L> + Sync,
+    N::Data: Sync,
+    IterData: IterationData<L, N>,
+{
+    /// Search the rewrites in parallel using [`rayon`](https://docs.rs/rayon).
+    /// Default: false
+    ///
+    /// The search of a single rewrite is also split across e-classes if its
+    /// [`Searcher`] lists them with
+    /// [`eclasses_to_search`](Searcher::eclasses_to_search), like
+    /// [`Pattern`] does.
+    ///
+    /// This only has an effect if the [`RewriteScheduler`] opts in with
+    /// [`can_search_in_parallel`](RewriteScheduler::can_search_in_parallel),
+    /// like [`SimpleScheduler`] and [`BackoffScheduler`] do.
+    /// The matches are exactly the same as with the sequential search,
+    /// so runs stay deterministic.
+    /// The limits are only checked once all the rewrites are searched.
+    ///
+    /// Requires the `parallel` feature.
+    #[cfg_attr(docsrs, doc(cfg(feature = "parallel")))]
+    pub fn with_parallel_search(self, parallel_search: bool) -> Self {
+        let parallel_search = if parallel_search {
+            Some(search_in_parallel as ParallelSearchFn<L, N>)
+        } else {
+            None
+        };
+        Self {
+            parallel_search,
+            ..self
+        }
+    }
 }
 
 fn check_rules<L, N>(rules: &[&Rewrite<L, N>]) {
@@ -662,15 +1686,102 @@ where
     /// A hook allowing you to customize rewrite searching behavior.
     /// Useful to implement rule management.
     ///
-    /// Default implementation just calls
-    /// [`Rewrite::search`](Rewrite::search()).
+    /// Default implementation calls
+    /// [`Rewrite::search`](Rewrite::search()) if
+    /// [`should_search_rewrite`](RewriteScheduler::should_search_rewrite())
+    /// says so, and passes the result through
+    /// [`filter_matches`](RewriteScheduler::filter_matches()).
     fn search_rewrite<'a>(
         &mut self,
         iteration: usize,
         egraph: &EGraph<L, N>,
         rewrite: &'a Rewrite<L, N>,
     ) -> Vec<SearchMatches<'a, L>> {
-        rewrite.search(egraph)
+        if self.should_search_rewrite(iteration, rewrite) {
+            let matches = rewrite.search(egraph);
+            self.filter_matches(iteration, rewrite, matches)
+                .unwrap_or_default()
+        } else {
+            vec![]
+        }
+    }
+
+    /// Like [`search_rewrite`](RewriteScheduler::search_rewrite()), but
+    /// called instead of it when the [`Runner`] does
+    /// [incremental search](Runner::with_incremental_search).
+    /// Only the matches that are new since the given
+    /// [generation](EGraph::generation) need to be returned.
+    ///
+    /// Return `None` if the rewrite was not searched or its matches were
+    /// dropped, so the [`Runner`] knows that it still has to look for
+    /// matches since `since` next time.
+    ///
+    /// Default implementation is like the one for
+    /// [`search_rewrite`](RewriteScheduler::search_rewrite()),
+    /// but calls [`Rewrite::search_since`](Rewrite::search_since()).
+    fn search_rewrite_since<'a>(
+        &mut self,
+        iteration: usize,
+        egraph: &EGraph<L, N>,
+        rewrite: &'a Rewrite<L, N>,
+        since: usize,
+    ) -> Option<Vec<SearchMatches<'a, L>>> {
+        if self.should_search_rewrite(iteration, rewrite) {
+            let matches = rewrite.search_since(egraph, since);
+            self.filter_matches(iteration, rewrite, matches)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the [`Runner`] may search the rewrites in parallel when
+    /// [parallel search](Runner::with_parallel_search) is enabled.
+    ///
+    /// Return `true` only if this scheduler's searching behavior is
+    /// entirely described by
+    /// [`should_search_rewrite`](RewriteScheduler::should_search_rewrite())
+    /// and [`filter_matches`](RewriteScheduler::filter_matches()),
+    /// since the parallel search calls those instead of
+    /// [`search_rewrite`](RewriteScheduler::search_rewrite()).
+    /// The parallel search asks about all the rewrites before searching,
+    /// and then filters their matches in order, so the results are the same
+    /// as long as the decisions about one rewrite don't depend on the others.
+    ///
+    /// Default implementation returns `false`.
+    fn can_search_in_parallel(&self) -> bool {
+        false
+    }
+
+    /// Decides whether to search a rewrite in this iteration.
+    ///
+    /// Default implementation returns `true`.
+    fn should_search_rewrite(&mut self, iteration: usize, rewrite: &Rewrite<L, N>) -> bool {
+        true
+    }
+
+    /// Looks at the matches a rewrite found, and decides which of them
+    /// to apply.
+    ///
+    /// Return `None` to drop all of them, in which case an
+    /// [incremental search](Runner::with_incremental_search) will look for
+    /// them again next time.
+    ///
+    /// Default implementation returns all the matches.
+    fn filter_matches<'a>(
+        &mut self,
+        iteration: usize,
+        rewrite: &'a Rewrite<L, N>,
+        matches: Vec<SearchMatches<'a, L>>,
+    ) -> Option<Vec<SearchMatches<'a, L>>> {
+        Some(matches)
+    }
+
+    /// Returns how many times this scheduler has banned the given rewrite,
+    /// which the [`Runner`] reports in [`RuleStats::times_banned`].
+    ///
+    /// Default implementation returns 0.
+    fn times_banned(&self, rewrite: &Rewrite<L, N>) -> usize {
+        0
     }
 
     /// A hook allowing you to customize rewrite application behavior.
@@ -709,6 +1820,9 @@ where
     L: Language,
     N: Analysis<L>,
 {
+    fn can_search_in_parallel(&self) -> bool {
+        true
+    }
 }
 
 /// A [`RewriteScheduler`] that implements exponentional rule backoff.
@@ -727,11 +1841,11 @@ where
 pub struct BackoffScheduler {
     default_match_limit: usize,
     default_ban_length: usize,
-    stats: IndexMap<Symbol, RuleStats>,
+    stats: IndexMap<Symbol, BackoffStats>,
 }
 
 #[derive(Debug)]
-struct RuleStats {
+struct BackoffStats {
     times_applied: usize,
     banned_until: usize,
     times_banned: usize,
@@ -754,11 +1868,11 @@ impl BackoffScheduler {
         self
     }
 
-    fn rule_stats(&mut self, name: Symbol) -> &mut RuleStats {
+    fn rule_stats(&mut self, name: Symbol) -> &mut BackoffStats {
         if self.stats.contains_key(&name) {
             &mut self.stats[&name]
         } else {
-            self.stats.entry(name).or_insert(RuleStats {
+            self.stats.entry(name).or_insert(BackoffStats {
                 times_applied: 0,
                 banned_until: 0,
                 times_banned: 0,
@@ -844,23 +1958,36 @@ where
         }
     }
 
-    fn search_rewrite<'a>(
-        &mut self,
-        iteration: usize,
-        egraph: &EGraph<L, N>,
-        rewrite: &'a Rewrite<L, N>,
-    ) -> Vec<SearchMatches<'a, L>> {
-        let stats = self.rule_stats(rewrite.name);
+    fn can_search_in_parallel(&self) -> bool {
+        true
+    }
 
+    fn times_banned(&self, rewrite: &Rewrite<L, N>) -> usize {
+        self.stats
+            .get(&rewrite.name)
+            .map_or(0, |stats| stats.times_banned)
+    }
+
+    fn should_search_rewrite(&mut self, iteration: usize, rewrite: &Rewrite<L, N>) -> bool {
+        let stats = self.rule_stats(rewrite.name);
         if iteration < stats.banned_until {
             debug!(
                 "Skipping {} ({}-{}), banned until {}...",
                 rewrite.name, stats.times_applied, stats.times_banned, stats.banned_until,
             );
-            return vec![];
+            false
+        } else {
+            true
         }
+    }
 
-        let matches = rewrite.search(egraph);
+    fn filter_matches<'a>(
+        &mut self,
+        iteration: usize,
+        rewrite: &'a Rewrite<L, N>,
+        matches: Vec<SearchMatches<'a, L>>,
+    ) -> Option<Vec<SearchMatches<'a, L>>> {
+        let stats = self.rule_stats(rewrite.name);
         let total_len: usize = matches.iter().map(|m| m.substs.len()).sum();
         let threshold = stats.match_limit << stats.times_banned;
         if total_len > threshold {
@@ -876,10 +2003,10 @@ where
                 threshold,
                 total_len,
             );
-            vec![]
+            None
         } else {
             stats.times_applied += 1;
-            matches
+            Some(matches)
         }
     }
 }
@@ -0,0 +1,273 @@
+use std::fmt::{self, Debug};
+
+use crate::util::HashMap;
+use crate::{Analysis, AstDepth, ConstrainedExtractor, EGraph, Id, Language, RecExpr};
+
+/** Drawing random terms from the e-classes of an [`EGraph`].
+
+Where an [`Extractor`](crate::Extractor) finds the best term of an
+e-class, a `TermSampler` draws random ones, e.g. to get many equivalent
+programs for differential testing. There are two ways to sample:
+- [`sample_uniform`](TermSampler::sample_uniform) draws uniformly among all
+  the terms of an e-class up to a given size.
+- [`sample_weighted`](TermSampler::sample_weighted) walks down from the
+  e-class, picking an e-node in each e-class with probability proportional
+  to a given weight, as long as it leads to a term within a given depth.
+
+The randomness comes from a user-provided function returning random `u64`s,
+so you can use any seeded random number generator (e.g. `|| rng.gen()`
+with the `rand` crate) to make the samples reproducible.
+
+```
+use egg::*;
+
+let mut egraph = EGraph::<SymbolLang, ()>::default();
+let a = egraph.add_expr(&"(+ x y)".parse().unwrap());
+let b = egraph.add_expr(&"(+ y x)".parse().unwrap());
+let x = egraph.add_expr(&"x".parse().unwrap());
+let z = egraph.add_expr(&"(* x 1)".parse().unwrap());
+egraph.union(a, b);
+egraph.union(x, z);
+egraph.rebuild();
+
+// a tiny seeded generator (xorshift), just for this example
+let mut state = 42u64;
+let rng = move || {
+    state ^= state << 13;
+    state ^= state >> 7;
+    state ^= state << 17;
+    state
+};
+
+let mut sampler = TermSampler::new(&egraph, rng);
+for _ in 0..10 {
+    let term = sampler.sample_uniform(a, 5).unwrap();
+    assert!(term.as_ref().len() <= 5);
+    assert_eq!(egraph.lookup_expr(&term), Some(egraph.find(a)));
+
+    // prefer multiplications
+    let weight = |node: &SymbolLang| if node.op.as_str() == "*" { 10.0 } else { 1.0 };
+    let term = sampler.sample_weighted(a, 4, weight).unwrap();
+    assert!(AstDepth.cost_rec(&term) <= 4);
+    assert_eq!(egraph.lookup_expr(&term), Some(egraph.find(a)));
+}
+
+// there are no terms of size 2 or less
+assert_eq!(sampler.sample_uniform(a, 2), None);
+```
+**/
+pub struct TermSampler<'a, L: Language, N: Analysis<L>, R> {
+    egraph: &'a EGraph<L, N>,
+    rng: R,
+    // the number of terms of each e-class of each size
+    counts: HashMap<(Id, usize), f64>,
+    // the number of ways the children of an e-node can be filled in,
+    // starting from a given child, with a given number of e-nodes
+    ways: HashMap<(Id, usize, usize, usize), f64>,
+    // the depth of the shallowest term of each e-class
+    min_depths: Option<HashMap<Id, usize>>,
+}
+
+impl<'a, L: Language, N: Analysis<L>, R> Debug for TermSampler<'a, L, N, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TermSampler")
+            .field("egraph", self.egraph)
+            .field("counts", &self.counts)
+            .field("min_depths", &self.min_depths)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, L, N, R> TermSampler<'a, L, N, R>
+where
+    L: Language,
+    N: Analysis<L>,
+    R: FnMut() -> u64,
+{
+    /// Create a new `TermSampler` that draws randomness from `rng`.
+    pub fn new(egraph: &'a EGraph<L, N>, rng: R) -> Self {
+        Self {
+            egraph,
+            rng,
+            counts: HashMap::default(),
+            ways: HashMap::default(),
+            min_depths: None,
+        }
+    }
+
+    /// Draw a term uniformly at random (up to floating point precision)
+    /// among the terms of the e-class with at most `max_size` e-nodes.
+    ///
+    /// Returns `None` if there is no such term.
+    pub fn sample_uniform(&mut self, eclass: Id, max_size: usize) -> Option<RecExpr<L>> {
+        let eclass = self.egraph.find(eclass);
+        let sizes: Vec<f64> = (1..=max_size).map(|s| self.count(eclass, s)).collect();
+        let size = 1 + self.choose(&sizes)?;
+        let mut expr = RecExpr::default();
+        self.build_uniform(eclass, size, &mut expr);
+        Some(expr)
+    }
+
+    /// Draw a term from the e-class by picking an e-node in each e-class
+    /// with probability proportional to `weight`, among the e-nodes that
+    /// still allow a term of depth at most `max_depth`.
+    /// E-nodes with a weight of zero are never picked.
+    ///
+    /// Returns `None` if there is no such term, or if the walk reaches an
+    /// e-class where all the e-nodes that fit have a weight of zero.
+    pub fn sample_weighted(
+        &mut self,
+        eclass: Id,
+        max_depth: usize,
+        mut weight: impl FnMut(&L) -> f64,
+    ) -> Option<RecExpr<L>> {
+        let egraph = self.egraph;
+        let min_depths = self.min_depths.get_or_insert_with(|| {
+            let mut extractor = ConstrainedExtractor::new(egraph, AstDepth);
+            egraph
+                .classes()
+                .filter_map(|c| Some((c.id, extractor.find_best_cost(c.id).ok()?)))
+                .collect()
+        });
+        let eclass = egraph.find(eclass);
+        if min_depths.get(&eclass).map_or(true, |&d| d > max_depth) {
+            return None;
+        }
+        let mut expr = RecExpr::default();
+        self.build_weighted(eclass, max_depth, &mut weight, &mut expr)?;
+        Some(expr)
+    }
+
+    /// A random number in `[0, 1)`.
+    fn random(&mut self) -> f64 {
+        ((self.rng)() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Picks an index with probability proportional to its weight.
+    fn choose(&mut self, weights: &[f64]) -> Option<usize> {
+        let total: f64 = weights.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut target = self.random() * total;
+        let mut last = None;
+        for (i, &w) in weights.iter().enumerate() {
+            if w > 0.0 {
+                if target < w {
+                    return Some(i);
+                }
+                target -= w;
+                last = Some(i);
+            }
+        }
+        // only reachable through rounding errors
+        last
+    }
+
+    /// The number of terms of the e-class with exactly `size` e-nodes.
+    fn count(&mut self, eclass: Id, size: usize) -> f64 {
+        if let Some(&count) = self.counts.get(&(eclass, size)) {
+            return count;
+        }
+        let mut count = 0.0;
+        if size > 0 {
+            for i in 0..self.egraph[eclass].len() {
+                count += self.ways(eclass, i, 0, size - 1);
+            }
+        }
+        self.counts.insert((eclass, size), count);
+        count
+    }
+
+    /// The number of ways to fill in the children of the `node`th e-node of
+    /// the e-class, from the `child`th one on, with `size` e-nodes in total.
+    fn ways(&mut self, eclass: Id, node: usize, child: usize, size: usize) -> f64 {
+        let key = (eclass, node, child, size);
+        if let Some(&ways) = self.ways.get(&key) {
+            return ways;
+        }
+        let children = self.egraph[eclass].nodes[node].children();
+        let ways = if child == children.len() {
+            if size == 0 {
+                1.0
+            } else {
+                0.0
+            }
+        } else {
+            let child_class = self.egraph.find(children[child]);
+            let rest = children.len() - child - 1;
+            let mut ways = 0.0;
+            for child_size in 1..=size.saturating_sub(rest) {
+                let count = self.count(child_class, child_size);
+                if count > 0.0 {
+                    ways += count * self.ways(eclass, node, child + 1, size - child_size);
+                }
+            }
+            ways
+        };
+        self.ways.insert(key, ways);
+        ways
+    }
+
+    fn build_uniform(&mut self, eclass: Id, size: usize, expr: &mut RecExpr<L>) -> Id {
+        let egraph = self.egraph;
+        let n_nodes = egraph[eclass].len();
+        let weights: Vec<f64> = (0..n_nodes)
+            .map(|i| self.ways(eclass, i, 0, size - 1))
+            .collect();
+        let i = self.choose(&weights).unwrap();
+        let node = &egraph[eclass].nodes[i];
+
+        let mut left = size - 1;
+        let mut ids = vec![];
+        for (c, &child) in node.children().iter().enumerate() {
+            let child = egraph.find(child);
+            let rest = node.len() - c - 1;
+            let sizes: Vec<f64> = (1..=left.saturating_sub(rest))
+                .map(|s| self.count(child, s) * self.ways(eclass, i, c + 1, left - s))
+                .collect();
+            let child_size = 1 + self.choose(&sizes).unwrap();
+            ids.push(self.build_uniform(child, child_size, expr));
+            left -= child_size;
+        }
+
+        let mut ids = ids.into_iter();
+        expr.add(node.clone().map_children(|_| ids.next().unwrap()))
+    }
+
+    fn build_weighted(
+        &mut self,
+        eclass: Id,
+        max_depth: usize,
+        weight: &mut impl FnMut(&L) -> f64,
+        expr: &mut RecExpr<L>,
+    ) -> Option<Id> {
+        let egraph = self.egraph;
+        let min_depths = self.min_depths.as_ref().unwrap();
+        let fits = |node: &L| {
+            node.all(|c| {
+                let depth = min_depths.get(&egraph.find(c));
+                depth.map_or(false, |&d| d < max_depth)
+            })
+        };
+        let weights: Vec<f64> = egraph[eclass]
+            .iter()
+            .map(|node| {
+                if fits(node) {
+                    weight(node).max(0.0)
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        let node = &egraph[eclass].nodes[self.choose(&weights)?];
+
+        let mut ids = vec![];
+        for &child in node.children() {
+            let child = egraph.find(child);
+            ids.push(self.build_weighted(child, max_depth - 1, weight, expr)?);
+        }
+        let mut ids = ids.into_iter();
+        Some(expr.add(node.clone().map_children(|_| ids.next().unwrap())))
+    }
+}
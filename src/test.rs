@@ -63,17 +63,7 @@ pub fn test_runner<L, A>(
     let id = runner.egraph.find(*runner.roots.last().unwrap());
 
     if check_fn.is_none() {
-        let goals = goals.to_vec();
-        runner = runner.with_hook(move |r| {
-            if goals
-                .iter()
-                .all(|g: &Pattern<_>| g.search_eclass(&r.egraph, id).is_some())
-            {
-                Err("Done".into())
-            } else {
-                Ok(())
-            }
-        });
+        runner = runner.with_goal_patterns(id, goals);
     }
     let mut runner = runner.run(rules);
 
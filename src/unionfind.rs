@@ -47,6 +47,16 @@ impl UnionFind {
         *self.parent_mut(root2) = root1;
         root1
     }
+
+    /// Undoes a [`union`](UnionFind::union) by making `id` a leader again.
+    pub fn make_root(&mut self, id: Id) {
+        *self.parent_mut(id) = id;
+    }
+
+    /// Forgets all the sets made after the first `size`.
+    pub fn truncate(&mut self, size: usize) {
+        self.parents.truncate(size);
+    }
 }
 
 #[cfg(test)]
@@ -50,6 +50,26 @@ pub(crate) type IndexSet<K> = indexmap::IndexSet<K, BuildHasher>;
 pub(crate) type Instant = instant::Instant;
 pub(crate) type Duration = instant::Duration;
 
+/// The approximate number of bytes used by a hash table with the given
+/// capacity and entry type, not counting what the entries point to.
+pub(crate) fn hash_table_size<T>(capacity: usize) -> usize {
+    // hashbrown uses one control byte per bucket
+    capacity * (std::mem::size_of::<T>() + 1)
+}
+
+/// The number of bytes an enode's children use outside of the enode,
+/// e.g. when they are in a `Vec` instead of an array.
+pub(crate) fn children_heap_size<L: crate::Language>(node: &L) -> usize {
+    let children = node.children();
+    let start = node as *const L as usize;
+    let inline = start..start + std::mem::size_of::<L>();
+    if children.is_empty() || inline.contains(&(children.as_ptr() as usize)) {
+        0
+    } else {
+        std::mem::size_of_val(children)
+    }
+}
+
 pub(crate) fn concat_vecs<T>(to: &mut Vec<T>, mut from: Vec<T>) {
     if to.len() < from.len() {
         std::mem::swap(to, &mut from)
@@ -72,3 +72,45 @@ fn path2() {
     runner.egraph.check_not("(pred (path 4 1))");
     runner.egraph.check_not("(pred (path 3 1))");
 }
+
+#[test]
+fn path_explained() {
+    let mut egraph = EGraph::<Lang, ()>::default().with_explanations_enabled();
+    for fact in ["(edge 1 2)", "(edge 2 3)", "(edge 3 4)"] {
+        egraph.union_instantiations(
+            &"true".parse().unwrap(),
+            &fact.parse().unwrap(),
+            &Default::default(),
+            "fact",
+        );
+    }
+    let rules = vec![
+        multi_rewrite!("base-case"; "?x = true = (edge ?a ?b)" => "?x = (path ?a ?b)"),
+        multi_rewrite!("transitive"; "?x = true = (path ?a ?b) = (edge ?b ?c)" => "?x = (path ?a ?c)"),
+    ];
+    let mut runner = Runner::default().with_egraph(egraph).run(&rules);
+
+    let mut explanation =
+        runner.explain_equivalence(&"(path 1 4)".parse().unwrap(), &"true".parse().unwrap());
+    println!("{}", explanation);
+    assert!(explanation.get_flat_string().contains("(Premises"));
+
+    // only the facts can't be checked
+    let report = explanation.verify_proof(&rules).unwrap();
+    assert!(!report.unchecked.is_empty());
+    for unchecked in &report.unchecked {
+        assert_eq!(unchecked.rule, Symbol::from("fact"));
+        assert_eq!(unchecked.reason, UncheckedReason::UnknownRule);
+    }
+    explanation.check_proof(&rules);
+
+    let wrong: Vec<Rewrite<Lang, ()>> = vec![
+        multi_rewrite!("base-case"; "?x = true = (edge ?a ?b)" => "?x = (path ?b ?a)"),
+        multi_rewrite!("transitive"; "?x = true = (path ?a ?b) = (edge ?b ?c)" => "?x = (path ?a ?c)"),
+    ];
+    // base-case is only used to prove premises of transitive
+    assert!(matches!(
+        explanation.verify_proof(&wrong),
+        Err(ProofError::InvalidPremise { .. })
+    ));
+}
@@ -93,17 +93,10 @@ impl Analysis<Math> for ConstantFold {
     fn modify(egraph: &mut EGraph, id: Id) {
         let class = egraph[id].clone();
         if let Some((c, pat)) = class.data {
-            if egraph.are_explanations_enabled() {
-                egraph.union_instantiations(
-                    &pat,
-                    &format!("{}", c).parse().unwrap(),
-                    &Default::default(),
-                    "constant_fold".to_string(),
-                );
-            } else {
-                let added = egraph.add(Math::Constant(c));
-                egraph.union(id, added);
-            }
+            // union the exact enodes, since `id` may stand for another one
+            let from = egraph.add_instantiation_noncanonical(&pat, &Default::default());
+            let to = egraph.add_uncanonical(Math::Constant(c));
+            egraph.union_by_analysis(from, to, format!("constant_fold: {} = {}", pat, c));
             // to not prune, comment this out
             egraph[id].nodes.retain(|n| n.is_leaf());
 
@@ -329,6 +322,642 @@ fn assoc_mul_saturates() {
     assert!(matches!(runner.stop_reason, Some(StopReason::Saturated)));
 }
 
+#[test]
+fn math_search_since() {
+    let rules = rules();
+    let mut egraph = EGraph::default();
+    for expr in &[
+        "(d x (- (pow x 3) (* 7 (pow x 2))))",
+        "(+ (* y (+ x y)) (- (+ x 2) (+ x x)))",
+        "(i (* (cos x) x) x)",
+    ] {
+        egraph.add_expr(&expr.parse().unwrap());
+    }
+    egraph.rebuild();
+
+    type Match = (Id, Vec<Id>);
+    let canonical = |egraph: &EGraph, rule: &Rewrite, ms: &[SearchMatches<Math>]| {
+        let vars = rule.searcher.vars();
+        let mut set = std::collections::HashSet::<Match>::new();
+        for m in ms {
+            for subst in &m.substs {
+                let ids = vars.iter().map(|v| egraph.find(subst[*v])).collect();
+                set.insert((egraph.find(m.eclass), ids));
+            }
+        }
+        set
+    };
+
+    let mut since = 0;
+    let mut old: Vec<std::collections::HashSet<Match>> = vec![Default::default(); rules.len()];
+    for _ in 0..4 {
+        let generation = egraph.new_generation();
+        let mut all_matches = vec![];
+        for (rule, old) in rules.iter().zip(&mut old) {
+            let full = rule.search(&egraph);
+            let full_set = canonical(&egraph, rule, &full);
+            let incremental = canonical(&egraph, rule, &rule.search_since(&egraph, since));
+            let old_set: std::collections::HashSet<Match> = old
+                .iter()
+                .map(|(id, ids)| {
+                    (
+                        egraph.find(*id),
+                        ids.iter().map(|&i| egraph.find(i)).collect(),
+                    )
+                })
+                .collect();
+            for m in full_set.difference(&old_set) {
+                assert!(incremental.contains(m), "{} missed {:?}", rule.name, m);
+            }
+            if since > 0 {
+                assert!(incremental.len() <= full_set.len());
+            }
+            *old = full_set;
+            all_matches.push(full);
+        }
+        for (rule, ms) in rules.iter().zip(all_matches) {
+            rule.apply(&mut egraph, &ms);
+        }
+        egraph.rebuild();
+        since = generation;
+    }
+}
+
+egg::test_fn! {
+    math_simplify_factor_incremental, rules(),
+    runner = Runner::default().with_incremental_search(true),
+    "(* (+ x 3) (+ x 1))"
+    =>
+    "(+ (+ (* x x) (* 4 x)) 3)"
+}
+
+/// A runner for a derivative that takes many iterations to saturate,
+/// stopping after `iters` of them.
+fn saturated_math(iters: usize) -> Runner<Math, ConstantFold> {
+    let expr: RecExpr<Math> = "(d x (- (pow x 3) (* 7 (pow x 2))))".parse().unwrap();
+    Runner::default()
+        .with_iter_limit(iters)
+        .with_scheduler(BackoffScheduler::default().with_initial_match_limit(100))
+        .with_expr(&expr)
+}
+
+#[test]
+fn math_rule_stats() {
+    let rules = rules();
+    let runner = saturated_math(8).run(&rules);
+
+    for iteration in &runner.iterations {
+        assert_eq!(iteration.rule_stats.len(), rules.len());
+        for (name, stats) in &iteration.rule_stats {
+            assert_eq!(
+                stats.applied,
+                iteration.applied.get(name).copied().unwrap_or(0)
+            );
+            assert!(stats.applied <= stats.matches);
+        }
+    }
+
+    let report = runner.report();
+    assert_eq!(report.rule_stats.len(), rules.len());
+    let total_applied: usize = runner
+        .iterations
+        .iter()
+        .flat_map(|i| i.applied.values())
+        .sum();
+    assert_eq!(
+        report.rule_stats.values().map(|s| s.applied).sum::<usize>(),
+        total_applied
+    );
+    let comm_mul = &report.rule_stats[&Symbol::from("comm-mul")];
+    assert!(comm_mul.times_banned > 0);
+    assert!(comm_mul.search_time > 0.0);
+    assert!(report.to_string().contains("comm-mul"));
+
+    // every rule has an entry even if the iteration stops before searching
+    let runner = saturated_math(8).with_node_limit(0).run(&rules);
+    assert!(matches!(runner.stop_reason, Some(StopReason::NodeLimit(_))));
+    assert_eq!(runner.iterations[0].rule_stats.len(), rules.len());
+}
+
+#[test]
+fn math_resume() {
+    let rules = rules();
+    let at_once = saturated_math(8).run(&rules);
+
+    let resumed = saturated_math(3).run(&rules);
+    assert!(matches!(
+        resumed.stop_reason,
+        Some(StopReason::IterationLimit(3))
+    ));
+    // still over the limit, so it stops right away
+    let mut resumed = resumed.run(&rules);
+    assert_eq!(resumed.iterations.len(), 3);
+    assert!(resumed.step(&rules).is_some());
+    assert_eq!(resumed.iterations.len(), 3);
+
+    let mut resumed = resumed.with_iter_limit(5).run(&rules);
+    assert_eq!(resumed.iterations.len(), 5);
+    resumed = resumed.with_iter_limit(8);
+    while resumed.step(&rules).is_none() {}
+
+    assert_eq!(resumed.iterations.len(), at_once.iterations.len());
+    for (r, a) in resumed.iterations.iter().zip(&at_once.iterations) {
+        assert_eq!(r.applied, a.applied);
+        assert_eq!(r.egraph_nodes, a.egraph_nodes);
+        assert_eq!(r.egraph_classes, a.egraph_classes);
+    }
+    assert_eq!(resumed.egraph.total_size(), at_once.egraph.total_size());
+    assert_eq!(
+        format!("{:?}", resumed.stop_reason),
+        format!("{:?}", at_once.stop_reason)
+    );
+}
+
+#[test]
+fn math_memory_limit() {
+    let expr: RecExpr<Math> = "(d x (- (pow x 3) (* 7 (pow x 2))))".parse().unwrap();
+    let limit = 1 << 20;
+    let runner: Runner<Math, ConstantFold> = Runner::default()
+        .with_node_limit(usize::MAX)
+        .with_iter_limit(usize::MAX)
+        .with_time_limit(std::time::Duration::from_secs(60))
+        .with_memory_limit(limit)
+        .with_expr(&expr)
+        .run(&rules());
+
+    match runner.stop_reason {
+        Some(StopReason::MemoryLimit(memory)) => assert!(memory > limit),
+        reason => panic!("unexpected stop reason {:?}", reason),
+    }
+    assert!(runner.egraph.approx_memory_usage().total() > limit);
+}
+
+#[test]
+fn math_kbest_extract() {
+    let expr: RecExpr<Math> = "(+ (* x 2) (- y y))".parse().unwrap();
+    let runner: Runner<Math, ConstantFold> = Runner::default()
+        .with_iter_limit(5)
+        .with_expr(&expr)
+        .run(&rules());
+    let root = runner.roots[0];
+
+    let k = 20;
+    let extractor = KBestExtractor::new(&runner.egraph, AstSize, k);
+    let best = extractor.find_best_k(root);
+    assert_eq!(best.len(), k);
+    assert_eq!(
+        best[0].0,
+        Extractor::new(&runner.egraph, AstSize).find_best_cost(root)
+    );
+
+    let mut seen = std::collections::HashSet::new();
+    for (cost, expr) in &best {
+        assert_eq!(*cost, AstSize.cost_rec(expr));
+        assert_eq!(
+            runner.egraph.lookup_expr(expr),
+            Some(runner.egraph.find(root))
+        );
+        assert!(seen.insert(expr.to_string()), "{} extracted twice", expr);
+    }
+    assert!(best.windows(2).all(|w| w[0].0 <= w[1].0));
+    assert!(extractor.find_kth_best(root, k).is_none());
+}
+
+#[test]
+fn math_kbest_same_class_children() {
+    let mut egraph = egg::EGraph::<Math, ()>::default();
+    let x = egraph.add_expr(&"x".parse().unwrap());
+    let y = egraph.add_expr(&"y".parse().unwrap());
+    let root = egraph.add_expr(&"(+ x y)".parse().unwrap());
+    egraph.union(x, y);
+    egraph.rebuild();
+
+    let extractor = KBestExtractor::new(&egraph, AstSize, 5);
+    let mut best: Vec<String> = extractor
+        .find_best_k(root)
+        .into_iter()
+        .map(|(cost, expr)| {
+            assert_eq!(cost, 3);
+            expr.to_string()
+        })
+        .collect();
+    best.sort();
+    assert_eq!(best, ["(+ x x)", "(+ x y)", "(+ y x)", "(+ y y)"]);
+}
+
+#[test]
+fn math_incremental_extract() {
+    let rules = rules();
+    let mut runner = saturated_math(6).with_incremental_search(true);
+    let root = runner.roots[0];
+
+    let mut detached = Extractor::new(&runner.egraph, AstSize).detach();
+    while runner.step(&rules).is_none() {
+        let incremental = detached.attach(&runner.egraph);
+        let fresh = Extractor::new(&runner.egraph, AstSize);
+        for class in runner.egraph.classes() {
+            assert_eq!(
+                incremental.find_best_cost(class.id),
+                fresh.find_best_cost(class.id)
+            );
+        }
+        let (cost, best) = incremental.find_best(root);
+        assert_eq!(cost, AstSize.cost_rec(&best));
+        detached = incremental.detach();
+    }
+}
+
+/// [`AstSize`], counting how many e-nodes it is asked about.
+struct CountingAstSize(std::rc::Rc<std::cell::Cell<usize>>);
+
+impl CostFunction<Math> for CountingAstSize {
+    type Cost = usize;
+    fn cost<C>(&mut self, enode: &Math, costs: C) -> Self::Cost
+    where
+        C: FnMut(Id) -> Self::Cost,
+    {
+        self.0.set(self.0.get() + 1);
+        AstSize.cost(enode, costs)
+    }
+}
+
+#[test]
+fn math_attach_revisits_changed() {
+    let mut egraph = EGraph::default();
+    egraph.add_expr(&"(d x (- (pow x 3) (* 7 (pow x 2))))".parse().unwrap());
+    let small = egraph.add_expr(&"(+ y z)".parse().unwrap());
+    egraph.rebuild();
+
+    let calls = std::rc::Rc::new(std::cell::Cell::new(0));
+    egraph.new_generation();
+    let extractor = Extractor::new(&egraph, CountingAstSize(calls.clone()));
+    assert!(calls.get() >= egraph.total_number_of_nodes());
+    let detached = extractor.detach();
+
+    let y = egraph.add(Math::Symbol("y".into()));
+    egraph.union(small, y);
+    egraph.rebuild();
+
+    // only the merged e-class (and its parent, itself) is visited again
+    calls.set(0);
+    let extractor = detached.attach(&egraph);
+    assert!(calls.get() > 0);
+    assert!(calls.get() <= 2 * egraph[small].len());
+    assert_eq!(extractor.find_best(small).1.to_string(), "y");
+}
+
+#[test]
+fn math_constrained_extract() {
+    let runner = saturated_math(6).run(&rules());
+    let (egraph, root) = (&runner.egraph, runner.roots[0]);
+
+    // without constraints, this finds the same costs as the Extractor
+    let extractor = Extractor::new(egraph, AstSize);
+    let mut unconstrained = ConstrainedExtractor::new(egraph, AstSize);
+    for class in egraph.classes() {
+        assert_eq!(
+            unconstrained.find_best_cost(class.id),
+            Ok(extractor.find_best_cost(class.id))
+        );
+    }
+
+    let no_diff = |node: &Math| !matches!(node, Math::Diff(_));
+    let mut constrained = ConstrainedExtractor::new(egraph, AstSize)
+        .with_node_filter(no_diff)
+        .with_max_depth(4);
+    let (cost, best) = constrained.find_best(root).unwrap();
+    assert!(cost > extractor.find_best_cost(root));
+    assert_eq!(cost, AstSize.cost_rec(&best));
+    assert!(best.as_ref().iter().all(no_diff));
+    assert!(AstDepth.cost_rec(&best) <= 4);
+    assert_eq!(egraph.lookup_expr(&best), Some(egraph.find(root)));
+
+    let mut too_shallow = ConstrainedExtractor::new(egraph, AstSize)
+        .with_node_filter(no_diff)
+        .with_max_depth(3);
+    assert_eq!(
+        too_shallow.find_best(root).map(|(cost, _)| cost),
+        Err(ExtractionError::NoAdmissibleTerm(egraph.find(root)))
+    );
+}
+
+#[test]
+fn math_annotated_extract() {
+    let runner = saturated_math(6).run(&rules());
+    let (egraph, root) = (&runner.egraph, runner.roots[0]);
+
+    let mut extractor = Extractor::new(egraph, AstSize);
+    let tree = extractor.find_best_annotated(root);
+    println!("{}", tree);
+    assert_eq!(tree.expr.as_ref().len(), tree.nodes.len());
+    assert_eq!(AstSize.cost_rec(&tree.expr), extractor.find_best_cost(root));
+
+    for (i, node) in tree.nodes.iter().enumerate() {
+        let subterm = tree.expr[Id::from(i)].build_recexpr(|id| tree.expr[id].clone());
+        assert_eq!(node.cost, AstSize.cost_rec(&subterm));
+        assert_eq!(egraph.lookup_expr(&subterm), Some(node.eclass));
+        assert_eq!(node.rejected.len() + 1, egraph[node.eclass].len());
+        for (_, cost) in &node.rejected {
+            assert!(cost.map_or(true, |c| c >= node.cost));
+        }
+    }
+}
+
+#[test]
+fn math_extract_multiple() {
+    let exprs: Vec<RecExpr<Math>> = ["(+ (* x 2) (- y y))", "(* (+ x x) (+ x x))", "(+ x x)"]
+        .iter()
+        .map(|s| s.parse().unwrap())
+        .collect();
+    let mut runner: Runner<Math, ConstantFold> = Runner::default().with_iter_limit(5);
+    for expr in &exprs {
+        runner = runner.with_expr(expr);
+    }
+    let runner = runner.run(&rules());
+    let egraph = &runner.egraph;
+
+    let extractor = Extractor::new(egraph, AstSize);
+    let (expr, roots) = extractor.find_best_multiple(&runner.roots);
+    assert_eq!(roots.len(), runner.roots.len());
+    assert!(expr.is_dag());
+
+    let mut n_unshared = 0;
+    for (&root, &eclass) in roots.iter().zip(&runner.roots) {
+        let (cost, best) = extractor.find_best(eclass);
+        let term = expr[root].build_recexpr(|id| expr[id].clone());
+        assert_eq!(AstSize.cost_rec(&term), cost);
+        assert_eq!(egraph.lookup_expr(&term), Some(egraph.find(eclass)));
+        n_unshared += best.as_ref().len();
+    }
+    // the roots share subterms, so this is smaller than extracting them separately
+    assert!(expr.as_ref().len() < n_unshared);
+}
+
+/// `(+ a (+ b c))` with only commutativity and associativity of `+`.
+fn ac_sum() -> Runner<Math, ConstantFold> {
+    let ac: Vec<Rewrite> = rules()
+        .into_iter()
+        .filter(|r| ["comm-add", "assoc-add"].contains(&r.name.as_str()))
+        .collect();
+    let expr: RecExpr<Math> = "(+ a (+ b c))".parse().unwrap();
+    Runner::default().with_expr(&expr).run(&ac)
+}
+
+/// `(* (+ x 0) 2)`, where `x = (+ x 0)` makes a cycle.
+fn add_zero_cycle() -> Runner<Math, ConstantFold> {
+    let expr: RecExpr<Math> = "(* (+ x 0) 2)".parse().unwrap();
+    Runner::default()
+        .with_iter_limit(3)
+        .with_expr(&expr)
+        .run(&rules())
+}
+
+#[test]
+fn math_enumerate_terms() {
+    let runner = ac_sum();
+    let (egraph, root) = (&runner.egraph, runner.roots[0]);
+
+    // 3! orders of the operands, and 2 ways to associate them
+    let enumerator = TermEnumerator::new(egraph);
+    assert_eq!(enumerator.count(root), TermCount::Finite(12));
+    let terms: Vec<RecExpr<Math>> = enumerator.terms(root, usize::MAX).collect();
+    let distinct: std::collections::HashSet<String> = terms.iter().map(|t| t.to_string()).collect();
+    assert_eq!(distinct.len(), 12);
+    for term in &terms {
+        assert_eq!(term.as_ref().len(), 5);
+        assert_eq!(egraph.lookup_expr(term), Some(egraph.find(root)));
+    }
+
+    // x = (+ x 0) makes a cycle
+    let runner = add_zero_cycle();
+    let (egraph, root) = (&runner.egraph, runner.roots[0]);
+    let enumerator = TermEnumerator::new(egraph);
+    assert_eq!(enumerator.count(root), TermCount::Infinite);
+    let terms: Vec<RecExpr<Math>> = enumerator.terms(root, 7).collect();
+    assert!(terms.len() > 1);
+    assert!(terms
+        .windows(2)
+        .all(|w| w[0].as_ref().len() <= w[1].as_ref().len()));
+    for term in &terms {
+        assert!(term.as_ref().len() <= 7);
+        assert_eq!(egraph.lookup_expr(term), Some(egraph.find(root)));
+    }
+}
+
+fn xorshift(mut state: u64) -> impl FnMut() -> u64 {
+    move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    }
+}
+
+#[test]
+fn math_sample_terms() {
+    let runner = ac_sum();
+    let (egraph, root) = (&runner.egraph, runner.roots[0]);
+
+    // all 12 terms are about equally likely
+    let mut sampler = TermSampler::new(egraph, xorshift(1));
+    let mut seen = std::collections::HashMap::<String, usize>::new();
+    for _ in 0..1200 {
+        let term = sampler.sample_uniform(root, 10).unwrap();
+        assert_eq!(egraph.lookup_expr(&term), Some(egraph.find(root)));
+        *seen.entry(term.to_string()).or_default() += 1;
+    }
+    assert_eq!(seen.len(), 12);
+    assert!(seen.values().all(|&n| (50..150).contains(&n)), "{:?}", seen);
+
+    // the same seed gives the same terms
+    let samples = |seed| {
+        let mut sampler = TermSampler::new(egraph, xorshift(seed));
+        (0..20)
+            .map(|_| {
+                sampler
+                    .sample_weighted(root, 3, |_| 1.0)
+                    .unwrap()
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(samples(7), samples(7));
+    assert_ne!(samples(7), samples(8));
+
+    // x = (+ x 0) makes a cycle, so the bounds matter
+    let runner = add_zero_cycle();
+    let (egraph, root) = (&runner.egraph, runner.roots[0]);
+    let mut sampler = TermSampler::new(egraph, xorshift(3));
+    for _ in 0..100 {
+        let term = sampler.sample_uniform(root, 9).unwrap();
+        assert!(term.as_ref().len() <= 9);
+        assert_eq!(egraph.lookup_expr(&term), Some(egraph.find(root)));
+
+        let term = sampler.sample_weighted(root, 4, |_| 1.0).unwrap();
+        assert!(AstDepth.cost_rec(&term) <= 4);
+        assert_eq!(egraph.lookup_expr(&term), Some(egraph.find(root)));
+    }
+    assert_eq!(sampler.sample_weighted(root, 1, |_| 1.0), None);
+}
+
+struct SizeAndDepth;
+impl CostFunction<Math> for SizeAndDepth {
+    type Cost = (usize, usize);
+    fn cost<C>(&mut self, enode: &Math, mut costs: C) -> Self::Cost
+    where
+        C: FnMut(Id) -> Self::Cost,
+    {
+        enode.fold((1, 1), |(size, depth), id| {
+            let (s, d) = costs(id);
+            (size + s, depth.max(d + 1))
+        })
+    }
+}
+
+#[test]
+fn math_pareto_extract() {
+    let runner = saturated_math(6).run(&rules());
+    let (egraph, root) = (&runner.egraph, runner.roots[0]);
+
+    let extractor = ParetoExtractor::new(egraph, SizeAndDepth);
+    let frontier = extractor.frontier(root);
+    assert!(!frontier.is_empty());
+    for (i, (cost, expr)) in frontier.iter().enumerate() {
+        assert_eq!(*cost, SizeAndDepth.cost_rec(expr));
+        assert_eq!(egraph.lookup_expr(expr), Some(egraph.find(root)));
+        for (j, (other, _)) in frontier.iter().enumerate() {
+            assert!(i == j || !other.dominates(cost));
+        }
+    }
+
+    // the ends of the frontier are the best terms for each component
+    let smallest = extractor.find_best_by(root, |a, b| a.cmp(b)).0;
+    assert_eq!(
+        smallest.0,
+        Extractor::new(egraph, AstSize).find_best_cost(root)
+    );
+    let shallowest = extractor
+        .find_best_by(root, |a, b| (a.1, a.0).cmp(&(b.1, b.0)))
+        .0;
+    assert_eq!(
+        shallowest.1,
+        Extractor::new(egraph, AstDepth).find_best_cost(root)
+    );
+}
+
+#[test]
+fn math_explain_constant_fold() {
+    let start: RecExpr<Math> = "(* x (+ 2 3))".parse().unwrap();
+    let goal: RecExpr<Math> = "(* x 5)".parse().unwrap();
+    let mut runner: Runner<Math, ConstantFold> = Runner::default()
+        .with_explanations_enabled()
+        .with_expr(&start)
+        .with_expr(&goal)
+        .run(&rules());
+    assert_eq!(
+        runner.egraph.find(runner.roots[0]),
+        runner.egraph.find(runner.roots[1])
+    );
+
+    let mut explanation = runner.explain_equivalence(&start, &goal);
+    let flat = explanation.get_flat_string();
+    assert!(
+        flat.contains("(Analysis=> constant_fold: (+ 2 3) = 5 5)"),
+        "{}",
+        flat
+    );
+    assert!(explanation.explanation_trees.iter().any(|tree| {
+        tree.child_proofs
+            .iter()
+            .flatten()
+            .any(|child| child.kind == RewriteKind::Analysis)
+    }));
+
+    let report = explanation.verify_proof(&rules()).unwrap();
+    assert!(!report.unchecked.is_empty());
+    for step in &report.unchecked {
+        assert_eq!(step.reason, UncheckedReason::Analysis);
+        assert_eq!(step.rule.as_str(), "constant_fold: (+ 2 3) = 5");
+    }
+}
+
+/// Prints a term of a flat explanation without its rewrites.
+fn plain_term(term: &FlatTerm<Math>) -> String {
+    if term.children.is_empty() {
+        term.node.to_string()
+    } else {
+        let children: Vec<String> = term.children.iter().map(plain_term).collect();
+        format!("({} {})", term.node, children.join(" "))
+    }
+}
+
+/// Checks that each constant folding step between `prev` and `term`
+/// rewrites the term its reason is about, returning how many there are.
+fn check_constant_fold_steps(prev: &FlatTerm<Math>, term: &FlatTerm<Math>) -> usize {
+    if term.kind == RewriteKind::Analysis {
+        let (reason, from) = match (term.forward_rule, term.backward_rule) {
+            (Some(reason), _) => (reason, prev),
+            (None, Some(reason)) => (reason, term),
+            (None, None) => unreachable!(),
+        };
+        let folded = reason.as_str().strip_prefix("constant_fold: ").unwrap();
+        let lhs = folded.split(" = ").next().unwrap();
+        assert_eq!(plain_term(from), lhs);
+        return 1;
+    }
+    prev.children
+        .iter()
+        .zip(&term.children)
+        .map(|(prev, term)| check_constant_fold_steps(prev, term))
+        .sum()
+}
+
+#[test]
+fn math_explain_nested_constant_fold() {
+    let start: RecExpr<Math> = "(* x (+ (* 1 2) 3))".parse().unwrap();
+    let goal: RecExpr<Math> = "(* x 5)".parse().unwrap();
+    let mut egraph = EGraph::default().with_explanations_enabled();
+    egraph.add_expr(&start);
+    egraph.add_expr(&goal);
+    egraph.rebuild();
+
+    // (+ (* 1 2) 3) has to become (+ 2 3) before it can be folded to 5
+    let mut explanation = egraph.explain_equivalence(&start, &goal);
+    let flat = explanation.make_flat_explanation().clone();
+    let steps: usize = flat
+        .windows(2)
+        .map(|pair| check_constant_fold_steps(&pair[0], &pair[1]))
+        .sum();
+    assert!(steps >= 2, "{}", explanation.get_flat_string());
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn math_parallel_search() {
+    let run = |parallel: bool, incremental: bool| {
+        saturated_math(8)
+            .with_parallel_search(parallel)
+            .with_incremental_search(incremental)
+            .run(&rules())
+    };
+
+    for incremental in [false, true] {
+        let sequential = run(false, incremental);
+        let parallel = run(true, incremental);
+        assert_eq!(sequential.iterations.len(), parallel.iterations.len());
+        for (s, p) in sequential.iterations.iter().zip(&parallel.iterations) {
+            assert_eq!(s.applied, p.applied);
+            for (name, stats) in &s.rule_stats {
+                assert_eq!(stats.matches, p.rule_stats[name].matches);
+                assert_eq!(stats.times_banned, p.rule_stats[name].times_banned);
+            }
+            assert_eq!(s.egraph_nodes, p.egraph_nodes);
+            assert_eq!(s.egraph_classes, p.egraph_classes);
+        }
+        assert_eq!(sequential.egraph.total_size(), parallel.egraph.total_size());
+    }
+}
+
 #[cfg(feature = "lp")]
 #[test]
 fn math_lp_extract() {
@@ -43,3 +43,225 @@ fn simple_tests() {
     assert_eq!(simplify("(* 0 42)"), "0");
     assert_eq!(simplify("(+ 0 (* 1 foo))"), "foo");
 }
+
+#[test]
+fn simple_retain_reachable() {
+    let expr: RecExpr<SimpleLanguage> = "(+ 0 (* 1 foo))".parse().unwrap();
+    let runner = Runner::default()
+        .with_expr(&expr)
+        .with_retain_reachable(true)
+        .run(&make_rules());
+
+    let root = runner.roots[0];
+    assert_eq!(runner.egraph.find(root), root);
+    assert_eq!(runner.egraph.lookup_expr(&expr), Some(root));
+    let extractor = Extractor::new(&runner.egraph, AstSize);
+    assert_eq!(extractor.find_best(root).1.to_string(), "foo");
+}
+
+#[test]
+fn simple_phases() {
+    let rules = make_rules();
+    let (commute, simplify): (Vec<_>, Vec<_>) = rules
+        .iter()
+        .partition(|rw| rw.name.as_str().starts_with("commute"));
+    let expr: RecExpr<SimpleLanguage> = "(* 1 (+ 0 foo))".parse().unwrap();
+    let run = |rounds| {
+        Runner::default()
+            .with_expr(&expr)
+            .with_phase(Phase::new("simplify", simplify.iter().copied()))
+            .with_phase(Phase::new("commute", commute.iter().copied()).with_iter_limit(1))
+            .with_phase_rounds(rounds)
+            .run_phases()
+    };
+    let best = |runner: &Runner<SimpleLanguage, ()>| {
+        let extractor = Extractor::new(&runner.egraph, AstSize);
+        extractor.find_best(runner.roots[0]).1.to_string()
+    };
+
+    // the simplifications only apply after commuting
+    let runner = run(1);
+    assert_eq!(best(&runner), "(* 1 (+ 0 foo))");
+    assert!(matches!(
+        runner.stop_reason,
+        Some(StopReason::IterationLimit(1))
+    ));
+
+    let runner = run(10);
+    assert_eq!(best(&runner), "foo");
+    assert!(matches!(runner.stop_reason, Some(StopReason::Saturated)));
+    assert!(runner.phase_reports.len() < 20);
+    let report = runner.report();
+    assert_eq!(report.phases.len(), runner.phase_reports.len());
+    for (i, phase) in runner.phase_reports.iter().enumerate() {
+        assert_eq!(phase.round, i / 2);
+        let expected = if i % 2 == 0 { "simplify" } else { "commute" };
+        assert_eq!(phase.name.as_str(), expected);
+        let iterations = &runner.iterations[phase.first_iteration..][..phase.iterations];
+        assert!(iterations.iter().all(|i| i.phase == Some(phase.name)));
+    }
+    let n_iterations: usize = runner.phase_reports.iter().map(|p| p.iterations).sum();
+    assert_eq!(n_iterations, runner.iterations.len());
+}
+
+#[test]
+fn simple_phase_loop_back() {
+    let rules = make_rules();
+    let (commute, simplify): (Vec<_>, Vec<_>) = rules
+        .iter()
+        .partition(|rw| rw.name.as_str().starts_with("commute"));
+    let expr: RecExpr<SimpleLanguage> = "(* 1 (+ 0 foo))".parse().unwrap();
+    let runner = Runner::default()
+        .with_expr(&expr)
+        .with_phase(Phase::new("simplify", simplify.iter().copied()))
+        .with_phase(
+            Phase::new("commute", commute.iter().copied())
+                .with_iter_limit(1)
+                .with_loop_back("simplify", 10),
+        )
+        .run_phases();
+
+    let extractor = Extractor::new(&runner.egraph, AstSize);
+    assert_eq!(extractor.find_best(runner.roots[0]).1.to_string(), "foo");
+    // all in one round, until a pass of both phases applies nothing
+    let reports = &runner.phase_reports;
+    assert!(reports.len() > 2 && reports.len() < 20);
+    assert!(reports.iter().all(|p| p.round == 0));
+    let last_pass = &runner.iterations[reports[reports.len() - 2].first_iteration..];
+    assert!(last_pass.iter().all(|i| i.applied.is_empty()));
+}
+
+#[test]
+#[should_panic(expected = "loops back to nope, which is not an earlier phase")]
+fn simple_phase_loop_back_unknown() {
+    let rules = make_rules();
+    Runner::default()
+        .with_expr(&"(+ 0 foo)".parse().unwrap())
+        .with_phase(Phase::new("all", &rules).with_loop_back("nope", 1))
+        .run_phases();
+}
+
+#[test]
+#[should_panic(expected = "phase_rounds must be at least 1")]
+fn simple_phase_rounds_zero() {
+    Runner::<SimpleLanguage, ()>::default().with_phase_rounds(0);
+}
+
+#[test]
+fn simple_cancel() {
+    let token = CancellationToken::new();
+    let rules = make_rules();
+    let runner = Runner::default()
+        .with_expr(&"(+ 0 (* 1 foo))".parse().unwrap())
+        .with_cancellation_token(token.clone())
+        .with_hook(move |runner| {
+            if runner.iterations.len() == 1 {
+                token.cancel();
+            }
+            Ok(())
+        })
+        .run(&rules);
+    assert!(matches!(runner.stop_reason, Some(StopReason::Cancelled)));
+    assert_eq!(runner.iterations.len(), 2);
+    assert!(runner.iterations[1].applied.is_empty());
+    assert!(!runner.egraph.is_cancelled());
+
+    // still cancelled, until it gets a new token
+    let runner = runner.run(&rules);
+    assert_eq!(runner.iterations.len(), 2);
+    let runner = runner
+        .with_cancellation_token(CancellationToken::new())
+        .run(&rules);
+    assert!(matches!(runner.stop_reason, Some(StopReason::Saturated)));
+}
+
+#[test]
+fn simple_goals() {
+    let expr: RecExpr<SimpleLanguage> = "(+ 0 (* 1 foo))".parse().unwrap();
+    let unreachable: RecExpr<SimpleLanguage> = "(* foo foo)".parse().unwrap();
+    let simplified: RecExpr<SimpleLanguage> = "foo".parse().unwrap();
+    let runner = Runner::default().with_expr(&expr);
+    let root = runner.roots[0];
+    let runner = runner
+        .with_goal_equivalence(&expr, &unreachable)
+        .with_goal_patterns(root, &["(* 1 ?x)".parse().unwrap(), "foo".parse().unwrap()])
+        .with_goal_equivalence(&expr, &simplified)
+        .run(&make_rules());
+
+    // the pattern goal and the last goal are reached in the same iteration
+    assert!(matches!(
+        runner.stop_reason,
+        Some(StopReason::GoalReached(1))
+    ));
+    assert!(runner.iterations.len() < 3);
+    assert_eq!(
+        runner.egraph.lookup_expr(&expr),
+        runner.egraph.lookup_expr(&simplified)
+    );
+}
+
+#[test]
+fn simple_cancel_incremental() {
+    // cancels the given token when it is applied
+    struct CancelWhenApplied(CancellationToken, Pattern<SimpleLanguage>);
+    impl Applier<SimpleLanguage, ()> for CancelWhenApplied {
+        fn apply_one(
+            &self,
+            egraph: &mut EGraph<SimpleLanguage, ()>,
+            eclass: Id,
+            subst: &Subst,
+            searcher_ast: Option<&PatternAst<SimpleLanguage>>,
+            rule_name: Symbol,
+        ) -> Vec<Id> {
+            self.0.cancel();
+            self.1
+                .apply_one(egraph, eclass, subst, searcher_ast, rule_name)
+        }
+    }
+
+    let make_rules = |token: &CancellationToken| -> Vec<Rewrite<SimpleLanguage, ()>> {
+        vec![rewrite!("mul-1"; "(* ?a 1)" => {
+            CancelWhenApplied(token.clone(), "?a".parse().unwrap())
+        })]
+    };
+    let expr = "(+ (* a 1) (+ (* b 1) (* c 1)))".parse().unwrap();
+
+    // this runner doesn't have the token its rule cancels
+    let uncancelled = Runner::default()
+        .with_incremental_search(true)
+        .with_expr(&expr)
+        .run(&make_rules(&CancellationToken::new()));
+    assert!(matches!(
+        uncancelled.stop_reason,
+        Some(StopReason::Saturated)
+    ));
+
+    // the first application cancels the runner, skipping the other matches
+    let token = CancellationToken::new();
+    let rules = make_rules(&token);
+    let runner = Runner::default()
+        .with_incremental_search(true)
+        .with_cancellation_token(token)
+        .with_expr(&expr)
+        .run(&rules);
+    assert!(matches!(runner.stop_reason, Some(StopReason::Cancelled)));
+    assert_eq!(runner.iterations[0].applied[&Symbol::from("mul-1")], 1);
+
+    let runner = runner
+        .with_cancellation_token(CancellationToken::new())
+        .run(&rules);
+    assert!(matches!(runner.stop_reason, Some(StopReason::Saturated)));
+    for var in ["a", "b", "c"] {
+        let mul: RecExpr<SimpleLanguage> = format!("(* {} 1)", var).parse().unwrap();
+        let var: RecExpr<SimpleLanguage> = var.parse().unwrap();
+        assert_eq!(
+            runner.egraph.lookup_expr(&mul),
+            runner.egraph.lookup_expr(&var)
+        );
+    }
+    assert_eq!(runner.egraph.total_size(), uncancelled.egraph.total_size());
+    assert_eq!(
+        runner.egraph.number_of_classes(),
+        uncancelled.egraph.number_of_classes()
+    );
+}